@@ -0,0 +1,21 @@
+//! Generates data in parallel with `rayon`.
+//!
+//! `ThreadRng` cannot be sent to other threads, so each generator is given its own
+//! seeded `StdRng` instead, which also makes the output reproducible.
+
+use km_gen::{Generator, UniformRange};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+
+fn main() {
+    // Generators with a `Send` RNG are `Send`, so they can be moved to worker threads.
+    let generators: Vec<UniformRange<u32, StdRng>> = (0..8)
+        .map(|seed| UniformRange::with_rng(0, 100, StdRng::seed_from_u64(seed)))
+        .collect();
+    let chunks: Vec<Vec<u32>> = generators
+        .into_par_iter()
+        .map(|mut gen| gen.generate_n(1000))
+        .collect();
+    let total: u64 = chunks.iter().flatten().map(|&v| u64::from(v)).sum();
+    println!("generated {} values, sum {}", chunks.len() * 1000, total);
+}
@@ -0,0 +1,131 @@
+//! Derive macros of km-gen.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Expr, Fields, GenericParam};
+
+/// Derives `km_gen::RandomGen` for a struct or an enum.
+///
+/// For enums, a variant is selected uniformly and its fields are generated.
+/// Each field is generated with its type's `RandomGen` implementation, unless it
+/// is annotated with `#[km_gen(closure)]`. The closure is called with the RNG passed
+/// to `try_random_gen`, as a `&mut R`, and returns a generator of the field type,
+/// which generates the field:
+///
+/// ```ignore
+/// #[derive(RandomGen)]
+/// struct Packet {
+///     #[km_gen(|rng| UniformRange::with_rng(0, 1500, rng))]
+///     len: u16,
+///     #[km_gen(|_| Constant::new(4))]
+///     version: u8,
+/// }
+/// ```
+///
+/// Building the generator from the RNG keeps values reproducible when the RNG is
+/// seeded. The closure is called again for every generated value, so stateful
+/// generators such as `Counter` start over each time.
+///
+/// The attribute is named after the crate rather than `gen`, which is a reserved
+/// keyword since Rust 2024.
+#[proc_macro_derive(RandomGen, attributes(km_gen))]
+pub fn derive_random_gen(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+    match expand(&mut input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &mut DeriveInput) -> syn::Result<TokenStream2> {
+    for param in input.generics.params.iter_mut() {
+        if let GenericParam::Type(ty) = param {
+            ty.bounds.push(parse_quote!(::km_gen::RandomGen));
+        }
+    }
+    let name = &input.ident;
+    // Mixed-site hygiene keeps the RNG binding out of reach of user expressions
+    let rng = Ident::new("rng", Span::mixed_site());
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let ctor = construct(quote!(#name), &data.fields, &rng)?;
+            quote!(::core::option::Option::Some(#ctor))
+        }
+        Data::Enum(data) if data.variants.is_empty() => quote!(::core::option::Option::None),
+        Data::Enum(data) => {
+            let count = data.variants.len();
+            let arms = data
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| {
+                    let ident = &variant.ident;
+                    let ctor = construct(quote!(#name::#ident), &variant.fields, &rng)?;
+                    Ok(quote!(#index => ::core::option::Option::Some(#ctor),))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                match ::km_gen::__private::rand::Rng::gen_range(#rng, 0..#count) {
+                    #(#arms)*
+                    _ => ::core::unreachable!(),
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "RandomGen cannot be derived for unions",
+            ))
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::km_gen::RandomGen for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn try_random_gen<R>(#rng: &mut R) -> ::core::option::Option<Self>
+            where
+                R: ::km_gen::__private::rand::Rng + ?::core::marker::Sized,
+            {
+                #body
+            }
+        }
+    })
+}
+
+/// Builds an expression constructing `path` with randomly generated `fields`.
+fn construct(path: TokenStream2, fields: &Fields, rng: &Ident) -> syn::Result<TokenStream2> {
+    let values = fields
+        .iter()
+        .map(|field| {
+            let mut value = None;
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("km_gen"))
+            {
+                if value.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "duplicate `km_gen` attribute",
+                    ));
+                }
+                let closure: Expr = attr.parse_args()?;
+                value = Some(quote!(::km_gen::__private::generate_field(&mut *#rng, #closure)?));
+            }
+            let ty = &field.ty;
+            Ok(
+                value
+                    .unwrap_or_else(|| quote!(<#ty as ::km_gen::RandomGen>::try_random_gen(#rng)?)),
+            )
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(match fields {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#idents: #values),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+        Fields::Unit => path,
+    })
+}
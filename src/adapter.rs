@@ -0,0 +1,1569 @@
+use alloc::{boxed::Box, rc::Rc, sync::Arc, vec, vec::Vec};
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::{collections::HashMap, hash::Hash};
+
+use crate::Generator;
+#[cfg(feature = "std")]
+use crate::Maybe;
+
+/// Extension methods for composing generators.
+///
+/// This trait is implemented for every type implementing [`Generator`].
+pub trait GeneratorExt<T>: Generator<T> {
+    /// Transforms each generated value with `f`.
+    fn map<B, F>(self, f: F) -> Map<Self, F, T>
+    where
+        Self: Sized,
+        F: FnMut(T) -> B,
+    {
+        Map::new(self, f)
+    }
+
+    /// Uses each generated value to build another generator, and generates a value from it.
+    fn flat_map<G, F>(self, f: F) -> FlatMap<Self, F, T>
+    where
+        Self: Sized,
+        F: FnMut(T) -> G,
+    {
+        FlatMap::new(self, f)
+    }
+
+    /// Keeps only the generated values accepted by `predicate`.
+    ///
+    /// See [`Filter`] for the retry behavior.
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+    where
+        Self: Sized,
+        P: FnMut(&T) -> bool,
+    {
+        Filter::new(self, predicate)
+    }
+
+    /// Pairs each generated value with a value from `other`.
+    fn zip<U, G>(self, other: G) -> Zip<Self, G>
+    where
+        Self: Sized,
+        G: Generator<U>,
+    {
+        Zip::new(self, other)
+    }
+
+    /// Generates values from this generator until it fails, then from `other`.
+    fn chain<G>(self, other: G) -> Chain<Self, G>
+    where
+        Self: Sized,
+        G: Generator<T>,
+    {
+        Chain::new(self, other)
+    }
+
+    /// Calls `f` with a reference to each generated value, passing it on unchanged.
+    fn inspect<F>(self, f: F) -> Inspect<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&T),
+    {
+        Inspect::new(self, f)
+    }
+
+    /// Combines each generated value with values from two other generators.
+    fn zip3<U, V, G2, G3>(self, gen2: G2, gen3: G3) -> Zip3<Self, G2, G3>
+    where
+        Self: Sized,
+        G2: Generator<U>,
+        G3: Generator<V>,
+    {
+        Zip3::new(self, gen2, gen3)
+    }
+
+    /// Combines each generated value with values from three other generators.
+    fn zip4<U, V, W, G2, G3, G4>(self, gen2: G2, gen3: G3, gen4: G4) -> Zip4<Self, G2, G3, G4>
+    where
+        Self: Sized,
+        G2: Generator<U>,
+        G3: Generator<V>,
+        G4: Generator<W>,
+    {
+        Zip4::new(self, gen2, gen3, gen4)
+    }
+
+    /// Retries failed generations, making at most `attempts` attempts per call.
+    fn retry(self, attempts: usize) -> Retry<Self>
+    where
+        Self: Sized,
+    {
+        Retry::new(self, attempts)
+    }
+
+    /// Generates at most `n` values, then always fails.
+    fn take(self, n: usize) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take::new(self, n)
+    }
+
+    /// Always fails after the first failed generation.
+    fn fuse(self) -> Fuse<Self>
+    where
+        Self: Sized,
+    {
+        Fuse::new(self)
+    }
+
+    /// Transforms each generated value with the fallible `f`.
+    fn and_then<B, F>(self, f: F) -> AndThen<Self, F, T>
+    where
+        Self: Sized,
+        F: FnMut(T) -> Option<B>,
+    {
+        AndThen::new(self, f)
+    }
+
+    /// Wraps each generated value in an [`Rc`] so that it can be cloned cheaply.
+    fn shared(self) -> Shared<Self>
+    where
+        Self: Sized,
+    {
+        Shared::new(self)
+    }
+
+    /// Wraps each generated value in an [`Arc`] so that it can be cloned cheaply
+    /// and shared across threads.
+    fn shared_arc(self) -> SharedArc<Self>
+    where
+        Self: Sized,
+    {
+        SharedArc::new(self)
+    }
+
+    /// Folds each generated value into a running state, generating the output of `f`.
+    fn scan<S, B, F>(self, init: S, f: F) -> Scan<Self, S, F, T>
+    where
+        Self: Sized,
+        F: FnMut(&mut S, T) -> Option<B>,
+    {
+        Scan::new(self, init, f)
+    }
+
+    /// Allows looking at the next generated value with `peek` before generating it.
+    fn peekable(self) -> Peekable<Self, T>
+    where
+        Self: Sized,
+    {
+        Peekable::new(self)
+    }
+
+    /// Rejects values equal to the previously generated one.
+    fn no_consecutive_dup(self) -> NoConsecutiveDup<Self, T>
+    where
+        Self: Sized,
+        T: PartialEq + Clone,
+    {
+        NoConsecutiveDup::new(self)
+    }
+
+    /// Generates a new value only every `n` calls, repeating the previous value in between.
+    fn every_nth(self, n: usize) -> EveryNth<Self, T>
+    where
+        Self: Sized,
+        T: Clone,
+    {
+        EveryNth::new(self, n)
+    }
+
+    /// Cycles through `n1` values of this generator, then `n2` values of another one.
+    fn interleave<G>(self, other: G, n1: usize, n2: usize) -> Interleave<Self, G>
+    where
+        Self: Sized,
+        G: Generator<T>,
+    {
+        Interleave::new(self, other, n1, n2)
+    }
+
+    /// Clamps each generated value into the inclusive range `[min, max]`.
+    fn clamp(self, min: T, max: T) -> Clamp<Self, T>
+    where
+        Self: Sized,
+        T: PartialOrd + Clone,
+    {
+        Clamp::new(self, min, max)
+    }
+
+    /// Generates values only while `predicate` returns `true`, failing otherwise.
+    fn when<P>(self, predicate: P) -> When<Self, P>
+    where
+        Self: Sized,
+        P: FnMut() -> bool,
+    {
+        When::new(self, predicate)
+    }
+
+    /// Flattens generated batches into their individual values.
+    fn flatten<U>(self) -> Flatten<Self, U>
+    where
+        Self: Sized + Generator<Vec<U>>,
+    {
+        Flatten::new(self)
+    }
+
+    /// Fails with a probability of `prob`, clamped into `[0.0, 1.0]`, and delegates to
+    /// this generator otherwise.
+    #[cfg(feature = "std")]
+    fn maybe(self, prob: f64) -> Maybe<Self>
+    where
+        Self: Sized,
+    {
+        Maybe::new(self, prob)
+    }
+
+    /// Records every generated value, so that it can be replayed later.
+    fn record(self) -> Record<Self, T>
+    where
+        Self: Sized,
+        T: Clone,
+    {
+        Record::new(self)
+    }
+
+    /// Converts the generator into an iterator over generated values.
+    ///
+    /// The iterator ends when the generator returns `None`.
+    fn iter(self) -> GeneratorIter<Self, T>
+    where
+        Self: Sized,
+    {
+        GeneratorIter::new(self)
+    }
+
+    /// Boxes the generator, erasing its concrete type.
+    fn boxed<'a>(self) -> Box<dyn Generator<T> + 'a>
+    where
+        Self: Sized + 'a,
+    {
+        Box::new(self)
+    }
+}
+
+impl<T, G> GeneratorExt<T> for G where G: Generator<T> + ?Sized {}
+
+/// Map adapter.
+///
+/// Applies a closure to each value generated by the wrapped generator.
+pub struct Map<G, F, A> {
+    generator: G,
+    f: F,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<G, F, A> Map<G, F, A> {
+    /// Create a new map adapter.
+    pub fn new(generator: G, f: F) -> Self {
+        Self {
+            generator,
+            f,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A, B, G, F> Generator<B> for Map<G, F, A>
+where
+    G: Generator<A>,
+    F: FnMut(A) -> B,
+{
+    fn try_generate(&mut self) -> Option<B> {
+        self.generator.try_generate().map(&mut self.f)
+    }
+}
+
+/// Flat map adapter.
+///
+/// Builds a generator from each value generated by the wrapped generator, and
+/// generates a single value from it.
+pub struct FlatMap<G, F, A> {
+    generator: G,
+    f: F,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<G, F, A> FlatMap<G, F, A> {
+    /// Create a new flat map adapter.
+    pub fn new(generator: G, f: F) -> Self {
+        Self {
+            generator,
+            f,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A, B, G, F, G2> Generator<B> for FlatMap<G, F, A>
+where
+    G: Generator<A>,
+    F: FnMut(A) -> G2,
+    G2: Generator<B>,
+{
+    fn try_generate(&mut self) -> Option<B> {
+        let value = self.generator.try_generate()?;
+        (self.f)(value).try_generate()
+    }
+}
+
+/// Filter adapter.
+///
+/// Generates values from the wrapped generator, rejecting those for which the
+/// predicate returns `false`. Rejection sampling is bounded: each call makes at
+/// most `max_attempts` attempts (100 by default) and returns `None` if none of
+/// them is accepted, so a predicate that never holds does not block forever.
+pub struct Filter<G, P> {
+    generator: G,
+    predicate: P,
+    max_attempts: usize,
+}
+
+impl<G, P> Filter<G, P> {
+    /// Default maximum number of attempts per generation.
+    pub const DEFAULT_MAX_ATTEMPTS: usize = 100;
+
+    /// Create a new filter adapter.
+    pub fn new(generator: G, predicate: P) -> Self {
+        Self {
+            generator,
+            predicate,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+
+    /// Set the maximum number of attempts per generation.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+impl<T, G, P> Generator<T> for Filter<G, P>
+where
+    G: Generator<T>,
+    P: FnMut(&T) -> bool,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        for _ in 0..self.max_attempts {
+            if let Some(value) = self.generator.try_generate() {
+                if (self.predicate)(&value) {
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Zip adapter.
+///
+/// Generates a pair of values, one from each wrapped generator. Both generators
+/// are always called, even if the first one fails, so that they advance in
+/// lockstep.
+pub struct Zip<G1, G2> {
+    gen1: G1,
+    gen2: G2,
+}
+
+impl<G1, G2> Zip<G1, G2> {
+    /// Create a new zip adapter.
+    pub fn new(gen1: G1, gen2: G2) -> Self {
+        Self { gen1, gen2 }
+    }
+}
+
+impl<A, B, G1, G2> Generator<(A, B)> for Zip<G1, G2>
+where
+    G1: Generator<A>,
+    G2: Generator<B>,
+{
+    fn try_generate(&mut self) -> Option<(A, B)> {
+        let a = self.gen1.try_generate();
+        let b = self.gen2.try_generate();
+        a.zip(b)
+    }
+}
+
+/// Iterator over the values of a generator.
+///
+/// Each call to `next` forwards to `try_generate`, so the iterator ends at the
+/// first failed generation.
+///
+/// Most generators never fail, so the iterator is usually infinite. Bound it with
+/// [`Iterator::take`] or `break` explicitly, including when iterating with
+/// `for v in &mut generator`:
+///
+/// ```
+/// use km_gen::Counter;
+///
+/// let mut counter = Counter::new(0u32);
+/// let values: Vec<u32> = (&mut counter).into_iter().take(3).collect();
+/// assert_eq!(values, [0, 1, 2]);
+/// for v in &mut counter {
+///     if v >= 5 {
+///         break;
+///     }
+/// }
+/// ```
+pub struct GeneratorIter<G, T> {
+    generator: G,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<G, T> GeneratorIter<G, T> {
+    /// Create a new generator iterator.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<G, T> Iterator for GeneratorIter<G, T>
+where
+    G: Generator<T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.generator.try_generate()
+    }
+}
+
+/// Storage of generated values tallied by [`Counting`].
+pub trait Tally<T> {
+    /// Record a generated value.
+    fn record(&mut self, value: &T);
+}
+
+impl<T> Tally<T> for () {
+    fn record(&mut self, _value: &T) {}
+}
+
+#[cfg(feature = "std")]
+impl<T> Tally<T> for HashMap<T, usize>
+where
+    T: Hash + Eq + Clone,
+{
+    fn record(&mut self, value: &T) {
+        *self.entry(value.clone()).or_insert(0) += 1;
+    }
+}
+
+/// Counting adapter.
+///
+/// Counts the successful and failed generations of the wrapped generator, and
+/// optionally keeps a histogram of the generated values.
+pub struct Counting<G, H = ()> {
+    generator: G,
+    successes: usize,
+    failures: usize,
+    histogram: H,
+}
+
+impl<G> Counting<G> {
+    /// Create a new counting adapter without histogram.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            successes: 0,
+            failures: 0,
+            histogram: (),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G, T> Counting<G, HashMap<T, usize>> {
+    /// Create a new counting adapter keeping a histogram of generated values.
+    pub fn with_histogram(generator: G) -> Self {
+        Self {
+            generator,
+            successes: 0,
+            failures: 0,
+            histogram: HashMap::new(),
+        }
+    }
+
+    /// Get the number of times each value has been generated.
+    pub fn histogram(&self) -> &HashMap<T, usize> {
+        &self.histogram
+    }
+}
+
+impl<G, H> Counting<G, H> {
+    /// Get the number of successful generations.
+    pub fn success_count(&self) -> usize {
+        self.successes
+    }
+
+    /// Get the number of failed generations.
+    pub fn failure_count(&self) -> usize {
+        self.failures
+    }
+}
+
+impl<T, G, H> Generator<T> for Counting<G, H>
+where
+    G: Generator<T>,
+    H: Tally<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.generator.try_generate();
+        match &value {
+            Some(value) => {
+                self.successes += 1;
+                self.histogram.record(value);
+            }
+            None => self.failures += 1,
+        }
+        value
+    }
+}
+
+/// Chain adapter.
+///
+/// Generates values from the first generator until it returns `None`, then
+/// switches permanently to the second one.
+pub struct Chain<G1, G2> {
+    gen1: Option<G1>,
+    gen2: G2,
+}
+
+impl<G1, G2> Chain<G1, G2> {
+    /// Create a new chain adapter.
+    pub fn new(gen1: G1, gen2: G2) -> Self {
+        Self {
+            gen1: Some(gen1),
+            gen2,
+        }
+    }
+}
+
+impl<T, G1, G2> Generator<T> for Chain<G1, G2>
+where
+    G1: Generator<T>,
+    G2: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        if let Some(gen1) = self.gen1.as_mut() {
+            match gen1.try_generate() {
+                Some(value) => return Some(value),
+                None => self.gen1 = None,
+            }
+        }
+        self.gen2.try_generate()
+    }
+}
+
+/// Inspect adapter.
+///
+/// Calls a closure on each value generated by the wrapped generator, without
+/// altering it. Failed generations skip the closure.
+pub struct Inspect<G, F> {
+    generator: G,
+    f: F,
+}
+
+impl<G, F> Inspect<G, F> {
+    /// Create a new inspect adapter.
+    pub fn new(generator: G, f: F) -> Self {
+        Self { generator, f }
+    }
+}
+
+impl<T, G, F> Generator<T> for Inspect<G, F>
+where
+    G: Generator<T>,
+    F: FnMut(&T),
+{
+    fn try_generate(&mut self) -> Option<T> {
+        self.generator.try_generate().inspect(&mut self.f)
+    }
+}
+
+/// Three-way zip adapter.
+///
+/// Generates a triple of values, one from each wrapped generator. Like [`Zip`],
+/// all generators are always called.
+pub struct Zip3<G1, G2, G3> {
+    gen1: G1,
+    gen2: G2,
+    gen3: G3,
+}
+
+impl<G1, G2, G3> Zip3<G1, G2, G3> {
+    /// Create a new three-way zip adapter.
+    pub fn new(gen1: G1, gen2: G2, gen3: G3) -> Self {
+        Self { gen1, gen2, gen3 }
+    }
+}
+
+impl<A, B, C, G1, G2, G3> Generator<(A, B, C)> for Zip3<G1, G2, G3>
+where
+    G1: Generator<A>,
+    G2: Generator<B>,
+    G3: Generator<C>,
+{
+    fn try_generate(&mut self) -> Option<(A, B, C)> {
+        let a = self.gen1.try_generate();
+        let b = self.gen2.try_generate();
+        let c = self.gen3.try_generate();
+        Some((a?, b?, c?))
+    }
+}
+
+/// Four-way zip adapter.
+///
+/// Generates a quadruple of values, one from each wrapped generator. Like [`Zip`],
+/// all generators are always called.
+pub struct Zip4<G1, G2, G3, G4> {
+    gen1: G1,
+    gen2: G2,
+    gen3: G3,
+    gen4: G4,
+}
+
+impl<G1, G2, G3, G4> Zip4<G1, G2, G3, G4> {
+    /// Create a new four-way zip adapter.
+    pub fn new(gen1: G1, gen2: G2, gen3: G3, gen4: G4) -> Self {
+        Self {
+            gen1,
+            gen2,
+            gen3,
+            gen4,
+        }
+    }
+}
+
+impl<A, B, C, D, G1, G2, G3, G4> Generator<(A, B, C, D)> for Zip4<G1, G2, G3, G4>
+where
+    G1: Generator<A>,
+    G2: Generator<B>,
+    G3: Generator<C>,
+    G4: Generator<D>,
+{
+    fn try_generate(&mut self) -> Option<(A, B, C, D)> {
+        let a = self.gen1.try_generate();
+        let b = self.gen2.try_generate();
+        let c = self.gen3.try_generate();
+        let d = self.gen4.try_generate();
+        Some((a?, b?, c?, d?))
+    }
+}
+
+/// Retry adapter.
+///
+/// Calls the wrapped generator up to `attempts` times, returning the first
+/// successfully generated value, or `None` if all attempts fail.
+pub struct Retry<G> {
+    generator: G,
+    attempts: usize,
+}
+
+impl<G> Retry<G> {
+    /// Create a new retry adapter.
+    pub fn new(generator: G, attempts: usize) -> Self {
+        Self {
+            generator,
+            attempts,
+        }
+    }
+}
+
+impl<T, G> Generator<T> for Retry<G>
+where
+    G: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        (0..self.attempts).find_map(|_| self.generator.try_generate())
+    }
+}
+
+/// Once adapter.
+///
+/// Generates a single value lazily from the wrapped generator and caches it,
+/// returning clones of it thereafter. A failed generation is not cached, so the
+/// next call tries again.
+pub struct Once<G, T> {
+    generator: G,
+    value: Option<T>,
+}
+
+impl<G, T> Once<G, T> {
+    /// Create a new once adapter.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            value: None,
+        }
+    }
+
+    /// Discard the cached value, so that the next call generates a new one.
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+impl<T, G> Generator<T> for Once<G, T>
+where
+    G: Generator<T>,
+    T: Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        if self.value.is_none() {
+            self.value = self.generator.try_generate();
+        }
+        self.value.clone()
+    }
+}
+
+impl_into_iterator! {
+    [G, T] Once<G, T> => T;
+}
+
+/// Take adapter.
+///
+/// Generates values from the wrapped generator until `n` values have been
+/// generated, then returns `None` forever. Failed generations pass through
+/// without consuming the budget.
+pub struct Take<G> {
+    generator: G,
+    remaining: usize,
+}
+
+impl<G> Take<G> {
+    /// Create a new take adapter.
+    pub fn new(generator: G, n: usize) -> Self {
+        Self {
+            generator,
+            remaining: n,
+        }
+    }
+}
+
+impl<T, G> Generator<T> for Take<G>
+where
+    G: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = self.generator.try_generate()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
+/// Fuse adapter.
+///
+/// Once the wrapped generator returns `None`, returns `None` forever without
+/// calling it again.
+pub struct Fuse<G> {
+    generator: Option<G>,
+}
+
+impl<G> Fuse<G> {
+    /// Create a new fuse adapter.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator: Some(generator),
+        }
+    }
+}
+
+impl<T, G> Generator<T> for Fuse<G>
+where
+    G: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.generator.as_mut()?.try_generate();
+        if value.is_none() {
+            self.generator = None;
+        }
+        value
+    }
+}
+
+/// And-then adapter.
+///
+/// Applies a fallible closure to each value generated by the wrapped generator,
+/// failing if either the generator or the closure fails.
+pub struct AndThen<G, F, A> {
+    generator: G,
+    f: F,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<G, F, A> AndThen<G, F, A> {
+    /// Create a new and-then adapter.
+    pub fn new(generator: G, f: F) -> Self {
+        Self {
+            generator,
+            f,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A, B, G, F> Generator<B> for AndThen<G, F, A>
+where
+    G: Generator<A>,
+    F: FnMut(A) -> Option<B>,
+{
+    fn try_generate(&mut self) -> Option<B> {
+        self.generator.try_generate().and_then(&mut self.f)
+    }
+}
+
+/// Shared adapter.
+///
+/// Wraps each value generated by the wrapped generator in an [`Rc`].
+pub struct Shared<G> {
+    generator: G,
+}
+
+impl<G> Shared<G> {
+    /// Create a new shared adapter.
+    pub fn new(generator: G) -> Self {
+        Self { generator }
+    }
+}
+
+impl<T, G> Generator<Rc<T>> for Shared<G>
+where
+    G: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<Rc<T>> {
+        self.generator.try_generate().map(Rc::new)
+    }
+}
+
+/// Thread-safe shared adapter.
+///
+/// Wraps each value generated by the wrapped generator in an [`Arc`].
+pub struct SharedArc<G> {
+    generator: G,
+}
+
+impl<G> SharedArc<G> {
+    /// Create a new thread-safe shared adapter.
+    pub fn new(generator: G) -> Self {
+        Self { generator }
+    }
+}
+
+impl<T, G> Generator<Arc<T>> for SharedArc<G>
+where
+    G: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<Arc<T>> {
+        self.generator.try_generate().map(Arc::new)
+    }
+}
+
+/// Scan adapter.
+///
+/// Calls a closure with a mutable state and each value generated by the wrapped
+/// generator, generating the closure's output.
+pub struct Scan<G, S, F, A> {
+    generator: G,
+    state: S,
+    f: F,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<G, S, F, A> Scan<G, S, F, A> {
+    /// Create a new scan adapter with the initial state.
+    pub fn new(generator: G, init: S, f: F) -> Self {
+        Self {
+            generator,
+            state: init,
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<A, B, G, S, F> Generator<B> for Scan<G, S, F, A>
+where
+    G: Generator<A>,
+    F: FnMut(&mut S, A) -> Option<B>,
+{
+    fn try_generate(&mut self) -> Option<B> {
+        let value = self.generator.try_generate()?;
+        (self.f)(&mut self.state, value)
+    }
+}
+
+/// Peekable adapter.
+///
+/// Buffers one generated value so that it can be inspected with `peek` before
+/// `try_generate` moves it out. A failed generation is buffered as well, so the
+/// `None` seen by `peek` is also returned by the next `try_generate`.
+pub struct Peekable<G, T> {
+    generator: G,
+    peeked: Option<Option<T>>,
+}
+
+impl<G, T> Peekable<G, T> {
+    /// Create a new peekable adapter.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            peeked: None,
+        }
+    }
+}
+
+impl<G, T> Peekable<G, T>
+where
+    G: Generator<T>,
+{
+    /// Get a reference to the next value without consuming it, generating it if needed.
+    pub fn peek(&mut self) -> Option<&T> {
+        let generator = &mut self.generator;
+        self.peeked
+            .get_or_insert_with(|| generator.try_generate())
+            .as_ref()
+    }
+}
+
+impl<T, G> Generator<T> for Peekable<G, T>
+where
+    G: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        match self.peeked.take() {
+            Some(value) => value,
+            None => self.generator.try_generate(),
+        }
+    }
+}
+
+impl_into_iterator! {
+    [G, T] Peekable<G, T> => T;
+}
+
+/// No consecutive duplicate adapter.
+///
+/// Remembers the last generated value and resamples the wrapped generator while it
+/// yields an equal value. Each call makes at most `max_attempts` attempts (100 by
+/// default) and returns `None` if none of them differs from the last value.
+pub struct NoConsecutiveDup<G, T> {
+    generator: G,
+    last: Option<T>,
+    max_attempts: usize,
+}
+
+impl<G, T> NoConsecutiveDup<G, T> {
+    /// Default maximum number of attempts per generation.
+    pub const DEFAULT_MAX_ATTEMPTS: usize = 100;
+
+    /// Create a new no consecutive duplicate adapter.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            last: None,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+
+    /// Set the maximum number of attempts per generation.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+impl<T, G> Generator<T> for NoConsecutiveDup<G, T>
+where
+    G: Generator<T>,
+    T: PartialEq + Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        for _ in 0..self.max_attempts {
+            if let Some(value) = self.generator.try_generate() {
+                if self.last.as_ref() != Some(&value) {
+                    self.last = Some(value.clone());
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl_into_iterator! {
+    [G, T] NoConsecutiveDup<G, T> => T;
+}
+
+/// Every nth adapter.
+///
+/// Generates a new value from the wrapped generator on the first call and then every
+/// `n` calls, returning a clone of the cached value in between. A failed generation
+/// is cached as well, so it repeats until the next regeneration. An `n` of zero is
+/// treated as one.
+pub struct EveryNth<G, T> {
+    generator: G,
+    n: usize,
+    calls: usize,
+    value: Option<T>,
+}
+
+impl<G, T> EveryNth<G, T> {
+    /// Create a new every nth adapter.
+    pub fn new(generator: G, n: usize) -> Self {
+        Self {
+            generator,
+            n: n.max(1),
+            calls: 0,
+            value: None,
+        }
+    }
+}
+
+impl<T, G> Generator<T> for EveryNth<G, T>
+where
+    G: Generator<T>,
+    T: Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        if self.calls == 0 {
+            self.value = self.generator.try_generate();
+        }
+        self.calls = (self.calls + 1) % self.n;
+        self.value.clone()
+    }
+}
+
+impl_into_iterator! {
+    [G, T] EveryNth<G, T> => T;
+}
+
+/// Interleave adapter.
+///
+/// Deterministically generates `n1` values from the first generator, then `n2`
+/// values from the second one, and repeats. A failed generation passes through and
+/// still counts towards the current generator's share. If both counts are zero,
+/// generation always fails.
+pub struct Interleave<G1, G2> {
+    gen1: G1,
+    gen2: G2,
+    n1: usize,
+    n2: usize,
+    pos: usize,
+}
+
+impl<G1, G2> Interleave<G1, G2> {
+    /// Create a new interleave adapter.
+    pub fn new(gen1: G1, gen2: G2, n1: usize, n2: usize) -> Self {
+        Self {
+            gen1,
+            gen2,
+            n1,
+            n2,
+            pos: 0,
+        }
+    }
+}
+
+impl<T, G1, G2> Generator<T> for Interleave<G1, G2>
+where
+    G1: Generator<T>,
+    G2: Generator<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        let period = self.n1.saturating_add(self.n2);
+        if period == 0 {
+            return None;
+        }
+        let pos = self.pos;
+        self.pos = (pos + 1) % period;
+        if pos < self.n1 {
+            self.gen1.try_generate()
+        } else {
+            self.gen2.try_generate()
+        }
+    }
+}
+
+/// Clamp adapter.
+///
+/// Pins each value generated by the wrapped generator into the inclusive range
+/// `[min, max]`: values below `min` become `min`, and values above `max` become
+/// `max`. Unlike [`Filter`], a value is never rejected. If `min > max`, values
+/// below `min` become `min` first.
+pub struct Clamp<G, T> {
+    generator: G,
+    min: T,
+    max: T,
+}
+
+impl<G, T> Clamp<G, T> {
+    /// Create a new clamp adapter.
+    pub fn new(generator: G, min: T, max: T) -> Self {
+        Self {
+            generator,
+            min,
+            max,
+        }
+    }
+}
+
+impl<T, G> Generator<T> for Clamp<G, T>
+where
+    G: Generator<T>,
+    T: PartialOrd + Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.generator.try_generate()?;
+        if value < self.min {
+            Some(self.min.clone())
+        } else if value > self.max {
+            Some(self.max.clone())
+        } else {
+            Some(value)
+        }
+    }
+}
+
+impl_into_iterator! {
+    [G, T] Clamp<G, T> => T;
+}
+
+/// When adapter.
+///
+/// Checks an external condition before each generation: if the predicate returns
+/// `true`, the wrapped generator is called, otherwise generation fails without
+/// calling it. Wrap the adapter in a [`DefaultOr`](crate::DefaultOr) to generate a
+/// fallback value while the condition does not hold.
+pub struct When<G, P> {
+    generator: G,
+    predicate: P,
+}
+
+impl<G, P> When<G, P> {
+    /// Create a new when adapter.
+    pub fn new(generator: G, predicate: P) -> Self {
+        Self {
+            generator,
+            predicate,
+        }
+    }
+}
+
+impl<T, G, P> Generator<T> for When<G, P>
+where
+    G: Generator<T>,
+    P: FnMut() -> bool,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        if (self.predicate)() {
+            self.generator.try_generate()
+        } else {
+            None
+        }
+    }
+}
+
+/// Flatten adapter.
+///
+/// Buffers a batch generated by the wrapped generator and generates its values one
+/// at a time, generating a new batch once the buffer is empty. Empty batches are
+/// skipped, so the wrapped generator should not generate them forever. Generation
+/// fails only when the buffer is empty and the wrapped generator fails.
+pub struct Flatten<G, T> {
+    generator: G,
+    buffer: vec::IntoIter<T>,
+}
+
+impl<G, T> Flatten<G, T> {
+    /// Create a new flatten adapter.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            buffer: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<T, G> Generator<T> for Flatten<G, T>
+where
+    G: Generator<Vec<T>>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        loop {
+            if let Some(value) = self.buffer.next() {
+                return Some(value);
+            }
+            self.buffer = self.generator.try_generate()?.into_iter();
+        }
+    }
+}
+
+impl_into_iterator! {
+    [G, T] Flatten<G, T> => T;
+}
+
+/// Replay adapter.
+///
+/// Generates recorded values in order, for example those captured by a [`Record`]
+/// adapter during a failing run, then delegates to the wrapped generator once they
+/// are exhausted.
+pub struct Replay<T, G> {
+    recorded: Vec<T>,
+    pos: usize,
+    generator: G,
+}
+
+impl<T, G> Replay<T, G> {
+    /// Create a new replay adapter.
+    pub fn new(recorded: Vec<T>, generator: G) -> Self {
+        Self {
+            recorded,
+            pos: 0,
+            generator,
+        }
+    }
+
+    /// Get the recorded values that have not been replayed yet.
+    pub fn recorded_remaining(&self) -> &[T] {
+        &self.recorded[self.pos..]
+    }
+}
+
+impl<T, G> Generator<T> for Replay<T, G>
+where
+    G: Generator<T>,
+    T: Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        match self.recorded.get(self.pos) {
+            Some(value) => {
+                self.pos += 1;
+                Some(value.clone())
+            }
+            None => self.generator.try_generate(),
+        }
+    }
+}
+
+impl_into_iterator! {
+    [T, G] Replay<T, G> => T;
+}
+
+/// Record adapter.
+///
+/// Passes the values generated by the wrapped generator through unchanged, and keeps
+/// a clone of each of them. Failed generations are not recorded. Feed the recorded
+/// values into a [`Replay`] adapter to reproduce the same sequence.
+pub struct Record<G, T> {
+    generator: G,
+    recorded: Vec<T>,
+}
+
+impl<G, T> Record<G, T> {
+    /// Create a new record adapter.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            recorded: Vec::new(),
+        }
+    }
+
+    /// Get the values generated so far, in order.
+    pub fn recorded(&self) -> &[T] {
+        &self.recorded
+    }
+
+    /// Consume the adapter and get the values generated so far.
+    pub fn into_recorded(self) -> Vec<T> {
+        self.recorded
+    }
+}
+
+impl<T, G> Generator<T> for Record<G, T>
+where
+    G: Generator<T>,
+    T: Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.generator.try_generate()?;
+        self.recorded.push(value.clone());
+        Some(value)
+    }
+}
+
+impl_into_iterator! {
+    [G, T] Record<G, T> => T;
+}
+
+/// Constrained pair adapter.
+///
+/// Generates a pair of values, one from each wrapped generator, and resamples both
+/// until the pair satisfies the relation. Each call makes at most `max_attempts`
+/// attempts (100 by default) and returns `None` if none of them is accepted.
+pub struct ConstrainedPair<G1, G2, F> {
+    gen1: G1,
+    gen2: G2,
+    relation: F,
+    max_attempts: usize,
+}
+
+impl<G1, G2, F> ConstrainedPair<G1, G2, F> {
+    /// Default maximum number of attempts per generation.
+    pub const DEFAULT_MAX_ATTEMPTS: usize = 100;
+
+    /// Create a new constrained pair adapter.
+    pub fn new(gen1: G1, gen2: G2, relation: F) -> Self {
+        Self {
+            gen1,
+            gen2,
+            relation,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+
+    /// Set the maximum number of attempts per generation.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+impl<A, B, G1, G2, F> Generator<(A, B)> for ConstrainedPair<G1, G2, F>
+where
+    G1: Generator<A>,
+    G2: Generator<B>,
+    F: Fn(&A, &B) -> bool,
+{
+    fn try_generate(&mut self) -> Option<(A, B)> {
+        for _ in 0..self.max_attempts {
+            let a = self.gen1.try_generate();
+            let b = self.gen2.try_generate();
+            if let (Some(a), Some(b)) = (a, b) {
+                if (self.relation)(&a, &b) {
+                    return Some((a, b));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Constant, Counter, FromFn, UniformCollection, UniformRange};
+    use core::cell::RefCell;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn iter_forwards_values_in_order() {
+        let values: Vec<u32> = Counter::new(0u32).iter().take(4).collect();
+        assert_eq!(values, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn iter_ends_at_first_failure() {
+        let mut calls = 0;
+        let gen = FromFn::new(move || {
+            calls += 1;
+            (calls != 3).then_some(calls)
+        });
+        let values: Vec<i32> = gen.iter().collect();
+        assert_eq!(values, [1, 2]);
+        let finite = Counter::new(0u8).with_max(2);
+        assert_eq!(finite.iter().collect::<Vec<_>>(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn empty_collection_yields_an_empty_iterator() {
+        let mut gen = UniformCollection::with_rng(Vec::<u8>::new(), StdRng::seed_from_u64(1));
+        assert_eq!((&mut gen).into_iter().next(), None);
+        assert_eq!(gen.iter().count(), 0);
+    }
+
+    #[test]
+    fn borrowed_generator_is_usable_after_iteration() {
+        let mut counter = Counter::new(0u32);
+        for value in &mut counter {
+            if value == 2 {
+                break;
+            }
+        }
+        assert_eq!(counter.generate(), 3);
+    }
+
+    #[test]
+    fn take_stops_after_n_values() {
+        let mut gen = Counter::new(0u32).take(3);
+        assert_eq!(gen.try_generate_n(3), Some(vec![0, 1, 2]));
+        assert_eq!(gen.try_generate(), None);
+        assert_eq!(gen.try_generate(), None);
+        assert_eq!(Counter::new(0u32).take(0).try_generate(), None);
+    }
+
+    #[test]
+    fn take_bounds_an_infinite_range() {
+        let rng = StdRng::seed_from_u64(2);
+        let mut gen = UniformRange::with_rng(-50i64, 50, rng).take(5);
+        let values = gen.generate_n(5);
+        assert!(values.iter().all(|v| (-50..50).contains(v)));
+        assert_eq!(gen.try_generate(), None);
+        let rng = StdRng::seed_from_u64(2);
+        let values: Vec<i64> = UniformRange::with_rng(-50i64, 50, rng)
+            .take(7)
+            .iter()
+            .collect();
+        assert_eq!(values.len(), 7);
+    }
+
+    #[test]
+    fn take_does_not_count_failures() {
+        let mut calls = 0;
+        let mut gen = FromFn::new(move || {
+            calls += 1;
+            (calls % 2 == 0).then_some(calls)
+        })
+        .take(2);
+        let values: Vec<_> = (0..6).map(|_| gen.try_generate()).collect();
+        assert_eq!(values, [None, Some(2), None, Some(4), None, None]);
+    }
+
+    #[test]
+    fn fuse_latches_the_first_failure() {
+        let mut calls = 0;
+        {
+            let mut gen = FromFn::new(|| {
+                calls += 1;
+                (calls != 2).then_some(calls)
+            })
+            .fuse();
+            assert_eq!(gen.try_generate(), Some(1));
+            assert_eq!(gen.try_generate(), None);
+            assert_eq!(gen.try_generate(), None);
+        }
+        // The inner generator would succeed again, but is never called after failing
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn fuse_stays_failed_after_the_collection_is_refilled() {
+        let collection = RefCell::new(UniformCollection::with_rng(
+            vec![1u8],
+            StdRng::seed_from_u64(3),
+        ));
+        let mut gen = FromFn::new(|| collection.borrow_mut().try_generate()).fuse();
+        assert_eq!(gen.try_generate(), Some(1));
+        collection.borrow_mut().clear();
+        assert_eq!(gen.try_generate(), None);
+        collection.borrow_mut().push(2);
+        assert_eq!(collection.borrow_mut().try_generate(), Some(2));
+        assert_eq!(gen.try_generate(), None);
+    }
+
+    #[test]
+    fn peek_does_not_consume_the_value() {
+        let mut gen = Counter::new(0u32).peekable();
+        assert_eq!(gen.peek(), Some(&0));
+        assert_eq!(gen.peek(), Some(&0));
+        assert_eq!(gen.try_generate(), Some(0));
+        assert_eq!(gen.try_generate(), Some(1));
+        assert_eq!(gen.peek(), Some(&2));
+        assert_eq!(gen.generate_n(2), [2, 3]);
+    }
+
+    #[test]
+    fn peeked_failure_is_returned_once() {
+        let mut calls = 0;
+        let mut gen = FromFn::new(move || {
+            calls += 1;
+            (calls != 1).then_some(calls)
+        })
+        .peekable();
+        assert_eq!(gen.peek(), None);
+        assert_eq!(gen.try_generate(), None);
+        assert_eq!(gen.try_generate(), Some(2));
+    }
+
+    #[test]
+    fn replay_reproduces_recorded_values() {
+        let rng = StdRng::seed_from_u64(7);
+        let mut gen = UniformRange::with_rng(0u32, 1000, rng).record();
+        let generated = gen.generate_n(20);
+        assert_eq!(gen.recorded(), generated);
+        let mut replay = Replay::new(gen.into_recorded(), Counter::new(0u32));
+        assert_eq!(replay.generate_n(5), generated[..5]);
+        assert_eq!(replay.recorded_remaining(), &generated[5..]);
+        assert_eq!(replay.generate_n(15), generated[5..]);
+        assert!(replay.recorded_remaining().is_empty());
+        assert_eq!(replay.generate_n(2), [0, 1]);
+    }
+
+    #[test]
+    fn record_skips_failures() {
+        let mut calls = 0;
+        let mut gen = FromFn::new(move || {
+            calls += 1;
+            (calls % 2 == 0).then_some(calls)
+        })
+        .record();
+        let generated: Vec<u32> = (0..6).filter_map(|_| gen.try_generate()).collect();
+        assert_eq!(generated, [2, 4, 6]);
+        assert_eq!(gen.into_recorded(), generated);
+    }
+
+    #[test]
+    fn constrained_pair_satisfies_relation() {
+        let gen1 = UniformRange::with_rng(0u32, 10, StdRng::seed_from_u64(8));
+        let gen2 = UniformRange::with_rng(0u32, 10, StdRng::seed_from_u64(9));
+        let mut gen = ConstrainedPair::new(gen1, gen2, |a: &u32, b: &u32| a < b);
+        for (a, b) in gen.generate_n(100) {
+            assert!(a < b);
+        }
+    }
+
+    #[test]
+    fn constrained_pair_gives_up_after_max_attempts() {
+        let at_least_5 = |a: &u32, _: &u32| *a >= 5;
+        let mut gen = ConstrainedPair::new(Counter::new(0u32), Constant::new(0u32), at_least_5)
+            .with_max_attempts(5);
+        assert_eq!(gen.try_generate(), None);
+        assert_eq!(gen.try_generate(), Some((5, 0)));
+        let mut gen = ConstrainedPair::new(Counter::new(0u32), Constant::new(0u32), at_least_5);
+        assert_eq!(gen.try_generate(), Some((5, 0)));
+        let mut gen = ConstrainedPair::new(Counter::new(5u32), Constant::new(0u32), at_least_5)
+            .with_max_attempts(0);
+        assert_eq!(gen.try_generate(), None);
+    }
+
+    #[test]
+    fn constrained_pair_fails_when_relation_is_impossible() {
+        let mut gen = ConstrainedPair::new(
+            Counter::new(0u32),
+            Counter::new(0u32),
+            |a: &u32, b: &u32| a != b,
+        );
+        assert_eq!(gen.try_generate(), None);
+        let mut gen = ConstrainedPair::new(
+            FromFn::new(|| None::<u32>),
+            Counter::new(0u32),
+            |_: &u32, _: &u32| true,
+        );
+        assert_eq!(gen.try_generate(), None);
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::vec::Vec;
+use rand::Rng;
+#[cfg(feature = "std")]
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{Constant, Generator, UniformRange, UniformRangeInclusive};
+
+/// Length generators whose maximum generated length is known.
+///
+/// Collection generators such as [`VecGenerator`] and
+/// [`BytesGenerator`](crate::BytesGenerator) use it to report the maximum length of the
+/// collections they generate.
+pub trait LenBound {
+    /// Get the maximum generated length, or `None` if no length can be generated.
+    fn max_len(&self) -> Option<usize>;
+}
+
+impl LenBound for Constant<usize> {
+    fn max_len(&self) -> Option<usize> {
+        Some(*self.get())
+    }
+}
+
+impl<R> LenBound for UniformRange<usize, R>
+where
+    R: Rng,
+{
+    fn max_len(&self) -> Option<usize> {
+        let (lb, ub) = (*self.lb(), *self.ub());
+        (lb < ub).then(|| ub - 1)
+    }
+}
+
+impl<R> LenBound for UniformRangeInclusive<usize, R>
+where
+    R: Rng,
+{
+    fn max_len(&self) -> Option<usize> {
+        let (lb, ub) = (*self.lb(), *self.ub());
+        (lb <= ub).then_some(ub)
+    }
+}
+
+/// A vector generator.
+///
+/// Generates vectors whose length is drawn from a length generator, and whose
+/// elements are drawn from an element generator. The vector is allocated with the
+/// drawn length up front, so filling it never reallocates.
+pub struct VecGenerator<LG, EG> {
+    len_gen: LG,
+    elem_gen: EG,
+}
+
+impl<LG, EG> VecGenerator<LG, EG> {
+    /// Create a new vector generator.
+    pub fn new(len_gen: LG, elem_gen: EG) -> Self {
+        Self { len_gen, elem_gen }
+    }
+}
+
+impl<LG, EG> VecGenerator<LG, EG>
+where
+    LG: LenBound,
+{
+    /// Get the maximum length of generated vectors, or `None` if the length generator
+    /// cannot generate any length.
+    pub fn max_len(&self) -> Option<usize> {
+        self.len_gen.max_len()
+    }
+}
+
+impl<T, LG, EG> Generator<Vec<T>> for VecGenerator<LG, EG>
+where
+    LG: Generator<usize>,
+    EG: Generator<T>,
+{
+    /// Generates a vector, returning `None` if the length or any element fails.
+    fn try_generate(&mut self) -> Option<Vec<T>> {
+        let len = self.len_gen.try_generate()?;
+        let mut vec = Vec::with_capacity(len);
+        for _ in 0..len {
+            vec.push(self.elem_gen.try_generate()?);
+        }
+        Some(vec)
+    }
+}
+
+/// A hash map generator.
+///
+/// Generates maps by drawing a size from a size generator, then inserting that many
+/// entries with keys and values drawn from their own generators. Colliding keys
+/// overwrite earlier entries, so a map may have fewer entries than the drawn size.
+#[cfg(feature = "std")]
+pub struct MapGenerator<SG, KG, VG> {
+    size_gen: SG,
+    key_gen: KG,
+    value_gen: VG,
+}
+
+#[cfg(feature = "std")]
+impl<SG, KG, VG> MapGenerator<SG, KG, VG> {
+    /// Create a new hash map generator.
+    pub fn new(size_gen: SG, key_gen: KG, value_gen: VG) -> Self {
+        Self {
+            size_gen,
+            key_gen,
+            value_gen,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, SG, KG, VG> Generator<HashMap<K, V>> for MapGenerator<SG, KG, VG>
+where
+    K: Hash + Eq,
+    SG: Generator<usize>,
+    KG: Generator<K>,
+    VG: Generator<V>,
+{
+    /// Generates a hash map, returning `None` if the size, any key or any value fails.
+    fn try_generate(&mut self) -> Option<HashMap<K, V>> {
+        let size = self.size_gen.try_generate()?;
+        let mut map = HashMap::with_capacity(size);
+        for _ in 0..size {
+            map.insert(self.key_gen.try_generate()?, self.value_gen.try_generate()?);
+        }
+        Some(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BytesGenerator;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    #[test]
+    fn max_len_follows_the_length_generator() {
+        let fixed = VecGenerator::new(Constant::new(4), Constant::new(0u8));
+        assert_eq!(fixed.max_len(), Some(4));
+        let range = VecGenerator::new(UniformRange::with_rng(2, 5, rng()), Constant::new(0u8));
+        assert_eq!(range.max_len(), Some(4));
+        let inclusive = UniformRangeInclusive::with_rng(2, 5, rng());
+        assert_eq!(
+            VecGenerator::new(inclusive, Constant::new(0u8)).max_len(),
+            Some(5)
+        );
+        let bytes = BytesGenerator::with_rng(UniformRangeInclusive::with_rng(0, 0, rng()), rng());
+        assert_eq!(bytes.max_len(), Some(0));
+    }
+
+    #[test]
+    fn max_len_of_empty_length_ranges_is_none() {
+        let range = VecGenerator::new(UniformRange::with_rng(3, 3, rng()), Constant::new(0u8));
+        assert_eq!(range.max_len(), None);
+        let inclusive = UniformRangeInclusive::with_rng(4, 3, rng());
+        assert_eq!(BytesGenerator::with_rng(inclusive, rng()).max_len(), None);
+    }
+
+    #[test]
+    fn generated_vectors_respect_max_len() {
+        let mut gen = VecGenerator::new(
+            UniformRangeInclusive::with_rng(0, 6, rng()),
+            Constant::new(1u8),
+        );
+        let max_len = gen.max_len().unwrap();
+        for vec in gen.generate_n(200) {
+            assert!(vec.len() <= max_len);
+            assert!(vec.iter().all(|&v| v == 1));
+        }
+    }
+}
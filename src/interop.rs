@@ -0,0 +1,89 @@
+use core::marker::PhantomData;
+#[cfg(feature = "proptest")]
+use core::{cell::RefCell, fmt};
+
+#[cfg(feature = "proptest")]
+use proptest::{
+    strategy::{Just, NewTree, Strategy},
+    test_runner::TestRunner,
+};
+#[cfg(feature = "quickcheck")]
+use quickcheck::{Arbitrary, Gen};
+
+use crate::Generator;
+
+/// A `proptest` strategy generating values from a generator.
+///
+/// Shrinking is not supported, generated values are kept unchanged.
+#[cfg(feature = "proptest")]
+pub struct AsStrategy<G, T> {
+    generator: RefCell<G>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "proptest")]
+impl<G, T> AsStrategy<G, T> {
+    /// Create a new strategy from a generator.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator: RefCell::new(generator),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl<G, T> fmt::Debug for AsStrategy<G, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsStrategy").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl<G, T> Strategy for AsStrategy<G, T>
+where
+    G: Generator<T>,
+    T: Clone + fmt::Debug,
+{
+    type Tree = Just<T>;
+    type Value = T;
+
+    fn new_tree(&self, _runner: &mut TestRunner) -> NewTree<Self> {
+        self.generator
+            .borrow_mut()
+            .try_generate()
+            .map(Just)
+            .ok_or_else(|| "Failed to generate value".into())
+    }
+}
+
+/// A generator of `quickcheck` arbitrary values.
+///
+/// `quickcheck` owns its RNG inside [`Gen`], so the `Gen` used to construct values must
+/// be supplied.
+#[cfg(feature = "quickcheck")]
+pub struct ArbitraryGen<T> {
+    gen: Gen,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T> ArbitraryGen<T> {
+    /// Create a new arbitrary generator using the specified `Gen`.
+    pub fn new(gen: Gen) -> Self {
+        Self {
+            gen,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T> Generator<T> for ArbitraryGen<T>
+where
+    T: Arbitrary,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        Some(T::arbitrary(&mut self.gen))
+    }
+}
@@ -1,8 +1,63 @@
 //! Command and argument generators of Kernel Model Check.
+//!
+//! The `std` feature is enabled by default. Without it, the crate is `no_std` (but still
+//! requires `alloc`), and an RNG must be supplied to each random generator explicitly.
+//!
+//! Random generators own their RNG, which is given to `with_rng` or is `ThreadRng` with
+//! `new`, and expose it through `rng_mut`, e.g. to reseed it. Mutating the RNG changes
+//! every value generated afterwards, so it affects reproducibility.
+//!
+//! Random generators are `Send` whenever their RNG is. The default `ThreadRng` is not, so
+//! supply a seeded RNG such as `StdRng` to move generators across threads; see the
+//! `parallel` example. Generators holding boxed trait objects or `Rc` are never `Send`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+
+/// Implements `IntoIterator` for `&mut` references to generators, so that `for v in &mut gen`
+/// iterates over [`GeneratorIter`]. Each entry lists the impl generics, the generator type,
+/// the generated type, and optionally extra where clauses in braces.
+///
+/// A blanket impl over all `G: Generator<T>` is rejected by coherence, so each generator
+/// type opts in here.
+macro_rules! impl_into_iterator {
+    ($([$($g:tt)*] $ty:ty => $item:ty $(where { $($w:tt)* })?;)*) => {
+        $(
+            impl<'a, $($g)*> IntoIterator for &'a mut $ty
+            where
+                $ty: $crate::Generator<$item>,
+                $($($w)*)?
+            {
+                type Item = $item;
+                type IntoIter = $crate::GeneratorIter<&'a mut $ty, $item>;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    $crate::GeneratorIter::new(self)
+                }
+            }
+        )*
+    };
+}
 
 /// Random-based generators.
 mod random;
 
+/// Generator adapters.
+mod adapter;
+
+/// Sequential generators.
+mod sequence;
+
+/// Collection generators.
+mod collection;
+
+/// Interoperability with other property testing crates.
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+mod interop;
+
 /// A generic value generator trait.
 pub trait Generator<T> {
     /// Try generates a value of type `T`, returning `None` if it fails.
@@ -12,9 +67,57 @@ pub trait Generator<T> {
     fn generate(&mut self) -> T {
         self.try_generate().expect("Failed to generate value")
     }
+
+    /// Try generates `n` values of type `T`, returning `None` at the first failure.
+    fn try_generate_n(&mut self, n: usize) -> Option<Vec<T>> {
+        (0..n).map(|_| self.try_generate()).collect()
+    }
+
+    /// Generates `n` values of type `T`, may panic if any of them fails.
+    fn generate_n(&mut self, n: usize) -> Vec<T> {
+        (0..n).map(|_| self.generate()).collect()
+    }
+
+    /// Appends up to `n` values of type `T` to `buf`, stopping at the first failure.
+    ///
+    /// Returns the number of values actually appended. Reusing `buf` across calls
+    /// avoids allocating a new vector each time.
+    fn generate_into(&mut self, buf: &mut Vec<T>, n: usize) -> usize {
+        for count in 0..n {
+            match self.try_generate() {
+                Some(value) => buf.push(value),
+                None => return count,
+            }
+        }
+        n
+    }
+}
+
+/// Boxed generators, including trait objects such as `Box<dyn Generator<T>>`,
+/// forward to the generator they contain.
+impl<T, G> Generator<T> for Box<G>
+where
+    G: Generator<T> + ?Sized,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        (**self).try_generate()
+    }
+}
+
+/// Mutable references forward to the generator they borrow, so a generator can be
+/// lent to an adapter or iterator and used again afterwards.
+impl<T, G> Generator<T> for &mut G
+where
+    G: Generator<T> + ?Sized,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        (**self).try_generate()
+    }
 }
 
 /// Constant generator.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug)]
 pub struct Constant<T>(T);
 
 impl<T> Constant<T> {
@@ -22,6 +125,10 @@ impl<T> Constant<T> {
     pub fn new(value: T) -> Self {
         Self(value)
     }
+    /// Get the value of the generator.
+    pub fn get(&self) -> &T {
+        &self.0
+    }
     /// Set the value of the generator.
     pub fn set(&mut self, value: T) {
         self.0 = value;
@@ -40,16 +147,34 @@ where
 /// Default-or generator.
 ///
 /// Generates the default value of type `T` if the wrapped generator fails.
-pub struct DefaultOr<T, G>(T, G);
+pub struct DefaultOr<T, G> {
+    default: T,
+    generator: G,
+    fell_back: usize,
+    last_used_default: bool,
+}
 
 impl<T, G> DefaultOr<T, G> {
     /// Create a new default-or generator.
     pub fn new(default: T, generator: G) -> Self {
-        Self(default, generator)
+        Self {
+            default,
+            generator,
+            fell_back: 0,
+            last_used_default: false,
+        }
     }
     /// Set the default value of the generator.
     pub fn set_default(&mut self, default: T) {
-        self.0 = default;
+        self.default = default;
+    }
+    /// Get the number of times the default value has been generated.
+    pub fn fell_back_count(&self) -> usize {
+        self.fell_back
+    }
+    /// Check if the most recent generation used the default value.
+    pub fn last_used_default(&self) -> bool {
+        self.last_used_default
     }
 }
 
@@ -59,8 +184,198 @@ where
     T: Clone,
 {
     fn try_generate(&mut self) -> Option<T> {
-        self.1.try_generate().or(Some(self.0.clone()))
+        let value = self.generator.try_generate();
+        self.last_used_default = value.is_none();
+        if self.last_used_default {
+            self.fell_back += 1;
+        }
+        value.or_else(|| Some(self.default.clone()))
     }
 }
 
-pub use random::{RandomFlags, RandomSwitch, SwitchConstant, UniformCollection, UniformRange};
+/// First-of generator.
+///
+/// Tries each generator in priority order and generates the first value that does
+/// not fail, so later generators are only used as fallbacks. Fails only if every
+/// generator fails, or if there is none.
+pub struct FirstOf<T> {
+    generators: Vec<Box<dyn Generator<T>>>,
+}
+
+impl<T> FirstOf<T> {
+    /// Create a new first-of generator with the generators in priority order.
+    pub fn new(generators: Vec<Box<dyn Generator<T>>>) -> Self {
+        Self { generators }
+    }
+    /// Add a generator with the lowest priority.
+    pub fn push<G>(&mut self, generator: G)
+    where
+        G: Generator<T> + 'static,
+    {
+        self.generators.push(Box::new(generator));
+    }
+}
+
+impl<T> Generator<T> for FirstOf<T> {
+    fn try_generate(&mut self) -> Option<T> {
+        self.generators
+            .iter_mut()
+            .find_map(|generator| generator.try_generate())
+    }
+}
+
+impl_into_iterator! {
+    [T] Constant<T> => T;
+    [T, G] DefaultOr<T, G> => T;
+    [T] FirstOf<T> => T;
+}
+
+pub use adapter::{
+    AndThen, Chain, Clamp, ConstrainedPair, Counting, EveryNth, Filter, FlatMap, Flatten, Fuse,
+    GeneratorExt, GeneratorIter, Inspect, Interleave, Map, NoConsecutiveDup, Once, Peekable,
+    Record, Replay, Retry, Scan, Shared, SharedArc, Take, Tally, When, Zip, Zip3, Zip4,
+};
+#[cfg(feature = "std")]
+pub use collection::MapGenerator;
+pub use collection::{LenBound, VecGenerator};
+#[cfg(feature = "quickcheck")]
+pub use interop::ArbitraryGen;
+#[cfg(feature = "proptest")]
+pub use interop::AsStrategy;
+pub use random::{
+    Bernoulli, BytesGenerator, DefaultRng, DistinctPair, DurationGenerator, FlagConfigError,
+    IpAddrGenerator, Maybe, Normal, OptionGenerator, Permutation, Random, RandomChoice,
+    RandomFlags, RandomGen, RandomSwitch, RandomSwitchMaybe, Reservoir, ResultGenerator,
+    SaturatingRange, ShuffledCycle, SteppedRange, StringGenerator, SwitchConstant, UniformChar,
+    UniformCollection, UniformCollectionNoReplace, UniformRange, UniformRangeF64,
+    UniformRangeInclusive, WeightedBy, WeightedCollection, WeightedSwitch,
+};
+pub use sequence::{Counter, CycleOrdered, FromFn, Product, Stateful};
+
+/// Derive macro generating a [`RandomGen`] implementation.
+#[cfg(feature = "derive")]
+pub use km_gen_derive::RandomGen;
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::Generator;
+
+    pub use rand;
+
+    /// Generates a field of a `RandomGen` derive from the generator built by `f`.
+    pub fn generate_field<'a, R, G, T>(rng: &'a mut R, f: impl FnOnce(&'a mut R) -> G) -> Option<T>
+    where
+        R: ?Sized,
+        G: Generator<T>,
+    {
+        f(rng).try_generate()
+    }
+}
+
+/// Builds a generator with an RNG seeded from a `u64`, for reproducible setups.
+///
+/// `seeded!(seed, Type::new(args...))` expands to
+/// `Type::with_rng(args..., StdRng::seed_from_u64(seed))`, so it works with every
+/// generator whose `with_rng` constructor takes the arguments of `new` followed by the RNG.
+/// Generic arguments given with a turbofish, as in `Random::<u32>::new()`, must omit the
+/// RNG type, which is always the last generic parameter.
+///
+/// ```
+/// use km_gen::{seeded, Generator, UniformRange};
+///
+/// let mut a = seeded!(42, UniformRange::new(0, 10));
+/// let mut b = seeded!(42, UniformRange::new(0, 10));
+/// assert_eq!(a.generate_n(8), b.generate_n(8));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! seeded {
+    ($seed:expr, $ty:ident $(::<$($generic:ty),+ $(,)?>)? :: new($($arg:expr),* $(,)?)) => {
+        $ty $(::<$($generic),+, _>)? ::with_rng(
+            $($arg,)*
+            <$crate::__private::rand::rngs::StdRng as $crate::__private::rand::SeedableRng>::seed_from_u64($seed),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn generate_n_returns_values_in_order() {
+        let mut counter = Counter::new(0u32);
+        assert_eq!(counter.generate_n(3), [0, 1, 2]);
+        assert!(counter.generate_n(0).is_empty());
+        assert_eq!(counter.generate(), 3);
+    }
+
+    #[test]
+    fn try_generate_n_fails_at_first_failure() {
+        let mut counter = Counter::new(0u8).with_max(2);
+        assert_eq!(counter.try_generate_n(2), Some(vec![0, 1]));
+        assert_eq!(counter.try_generate_n(2), None);
+        assert_eq!(counter.try_generate_n(0), Some(vec![]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to generate value")]
+    fn generate_n_panics_on_failure() {
+        Counter::new(0u8).with_max(1).generate_n(3);
+    }
+
+    #[test]
+    fn constant_generates_its_value() {
+        let mut gen = Constant::new(7u8);
+        assert_eq!(gen.generate_n(3), [7, 7, 7]);
+        gen.set(9);
+        assert_eq!(*gen.get(), 9);
+        assert_eq!(gen.generate(), 9);
+    }
+
+    #[test]
+    fn generators_are_send_with_a_send_rng() {
+        fn assert_send<T: Send>() {}
+        assert_send::<UniformRange<u32, StdRng>>();
+        assert_send::<RandomSwitch<Constant<u8>, UniformRange<u8, StdRng>, StdRng>>();
+        assert_send::<VecGenerator<UniformRange<usize, StdRng>, Bernoulli<StdRng>>>();
+        assert_send::<IpAddrGenerator<StdRng>>();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn seeded_generators_are_reproducible() {
+        let mut a = seeded!(1, Random::<u32>::new());
+        let mut b = seeded!(1, Random::<u32>::new());
+        assert_eq!(a.generate_n(20), b.generate_n(20));
+        let mut c = seeded!(2, Random::<u32>::new());
+        assert_ne!(a.generate_n(20), c.generate_n(20));
+
+        let mut a = seeded!(3, UniformRange::<u8>::new(0, 100));
+        let mut b: UniformRange<u8, StdRng> = seeded!(3, UniformRange::new(0, 100));
+        assert_eq!(a.generate_n(20), b.generate_n(20));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn seeded_flags_are_reproducible() {
+        bitflags::bitflags! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            struct Mode: u8 {
+                const READ = 1;
+                const WRITE = 1 << 1;
+                const EXEC = 1 << 2;
+            }
+        }
+
+        let mut a = seeded!(4, RandomFlags::<Mode>::new(0.5));
+        let mut b = seeded!(4, RandomFlags::<Mode>::new(0.5));
+        a.constraint(Mode::WRITE, Mode::READ);
+        b.constraint(Mode::WRITE, Mode::READ);
+        let values = a.generate_n(50);
+        assert_eq!(values, b.generate_n(50));
+        assert!(values.iter().any(|v| *v != values[0]));
+    }
+}
@@ -1,71 +1,429 @@
-use std::fmt::Debug;
+use alloc::{boxed::Box, collections::BTreeSet, string::String, vec, vec::Vec};
+use core::fmt::{self, Debug, Display, Formatter};
+use core::marker::PhantomData;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use core::time::Duration;
 
-use crate::{Constant, Generator};
+use crate::{Constant, Generator, LenBound};
 use bitflags::{Bits, Flags};
-use rand::{distributions::uniform::SampleUniform, rngs::ThreadRng, Rng};
+use num_traits::{Float, PrimInt};
+use rand::{
+    distributions::{uniform::SampleUniform, Distribution, WeightedIndex},
+    seq::{index::sample, SliceRandom},
+    Rng,
+};
+use rand_distr::StandardNormal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The RNG used by generators created with `new`.
+#[cfg(feature = "std")]
+pub type DefaultRng = rand::rngs::ThreadRng;
+
+/// Placeholder default RNG without the `std` feature.
+///
+/// It cannot be instantiated, so an RNG must be supplied with `with_rng`.
+#[cfg(not(feature = "std"))]
+pub enum DefaultRng {}
+
+/// Clamps a probability into `[0.0, 1.0]`, mapping `NaN` to `0.0`.
+fn clamp_prob(prob: f64) -> f64 {
+    if prob.is_nan() {
+        0.0
+    } else {
+        prob.clamp(0.0, 1.0)
+    }
+}
+
+/// Normalizes weights into probabilities, returning `None` if they sum to zero.
+fn normalize_weights(weights: impl Iterator<Item = f64> + Clone) -> Option<Vec<f64>> {
+    let total: f64 = weights.clone().sum();
+    (total > 0.0).then(|| weights.map(|weight| weight / total).collect())
+}
+
+/// Deserializes a probability, clamping it into `[0.0, 1.0]`.
+#[cfg(feature = "serde")]
+fn deserialize_prob<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    f64::deserialize(deserializer).map(clamp_prob)
+}
+
+/// Deserializes per-flag probabilities, clamping them into `[0.0, 1.0]`.
+#[cfg(feature = "serde")]
+fn deserialize_flag_probs<'de, D, T>(deserializer: D) -> Result<Vec<(T, f64)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let flag_probs = Vec::<(T, f64)>::deserialize(deserializer)?;
+    Ok(flag_probs
+        .into_iter()
+        .map(|(flag, prob)| (flag, clamp_prob(prob)))
+        .collect())
+}
 
 /// A uniform distribution range generator.
 ///
 /// This structure represents a range with a close lower bound (`lb`) and an
 /// open upper bound (`ub`), from which it generates a random value uniformly.
-pub struct UniformRange<T> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UniformRange<T, R = DefaultRng> {
     lb: T,
     ub: T,
-    rng: ThreadRng,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observed_min: Option<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observed_max: Option<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: R,
 }
 
+#[cfg(feature = "std")]
 impl<T> UniformRange<T>
 where
     T: Clone + PartialOrd,
 {
     /// Creates a new `UniformSampleRange` with the specified bounds.
     pub fn new(lb: T, ub: T) -> Self {
+        Self::with_rng(lb, ub, rand::thread_rng())
+    }
+}
+
+impl<T, R> UniformRange<T, R>
+where
+    T: Clone + PartialOrd,
+    R: Rng,
+{
+    /// Creates a new `UniformSampleRange` with the specified bounds and RNG.
+    pub fn with_rng(lb: T, ub: T, rng: R) -> Self {
         Self {
             lb,
             ub,
-            rng: rand::thread_rng(),
+            observed_min: None,
+            observed_max: None,
+            rng,
         }
     }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Get the lower bound of the range.
+    pub fn lb(&self) -> &T {
+        &self.lb
+    }
+
+    /// Get the upper bound of the range.
+    pub fn ub(&self) -> &T {
+        &self.ub
+    }
+
+    /// Set the lower bound of the range.
+    pub fn set_lb(&mut self, lb: T) {
+        self.lb = lb;
+    }
+
+    /// Set the upper bound of the range.
+    pub fn set_ub(&mut self, ub: T) {
+        self.ub = ub;
+    }
+
+    /// Set both bounds of the range.
+    pub fn set_bounds(&mut self, lb: T, ub: T) {
+        self.lb = lb;
+        self.ub = ub;
+    }
+
+    /// Get the smallest value generated so far.
+    pub fn observed_min(&self) -> Option<T> {
+        self.observed_min.clone()
+    }
+
+    /// Get the largest value generated so far.
+    pub fn observed_max(&self) -> Option<T> {
+        self.observed_max.clone()
+    }
+
+    /// Forget the values generated so far.
+    pub fn reset_observations(&mut self) {
+        self.observed_min = None;
+        self.observed_max = None;
+    }
 }
 
-impl<T> Generator<T> for UniformRange<T>
+impl<T, R> Generator<T> for UniformRange<T, R>
 where
     T: Clone + PartialOrd + SampleUniform,
+    R: Rng,
 {
     /// Generates a random sample within the specified bounds.
     fn try_generate(&mut self) -> Option<T> {
         if self.lb < self.ub {
-            Some(self.rng.gen_range(self.lb.clone()..self.ub.clone()))
+            let value = self.rng.gen_range(self.lb.clone()..self.ub.clone());
+            if self.observed_min.as_ref().is_none_or(|min| value < *min) {
+                self.observed_min = Some(value.clone());
+            }
+            if self.observed_max.as_ref().is_none_or(|max| value > *max) {
+                self.observed_max = Some(value.clone());
+            }
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, R> Debug for UniformRange<T, R>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UniformRange")
+            .field("lb", &self.lb)
+            .field("ub", &self.ub)
+            .field("observed_min", &self.observed_min)
+            .field("observed_max", &self.observed_max)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A uniform distribution inclusive range generator.
+///
+/// This structure represents a range with a close lower bound (`lb`) and a
+/// close upper bound (`ub`), from which it generates a random value uniformly.
+pub struct UniformRangeInclusive<T, R = DefaultRng> {
+    lb: T,
+    ub: T,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> UniformRangeInclusive<T>
+where
+    T: Clone + PartialOrd,
+{
+    /// Creates a new `UniformRangeInclusive` with the specified bounds.
+    pub fn new(lb: T, ub: T) -> Self {
+        Self::with_rng(lb, ub, rand::thread_rng())
+    }
+}
+
+impl<T, R> UniformRangeInclusive<T, R>
+where
+    T: Clone + PartialOrd,
+    R: Rng,
+{
+    /// Creates a new `UniformRangeInclusive` with the specified bounds and RNG.
+    pub fn with_rng(lb: T, ub: T, rng: R) -> Self {
+        Self { lb, ub, rng }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Get the lower bound of the range.
+    pub fn lb(&self) -> &T {
+        &self.lb
+    }
+
+    /// Get the upper bound of the range.
+    pub fn ub(&self) -> &T {
+        &self.ub
+    }
+}
+
+impl<T, R> Generator<T> for UniformRangeInclusive<T, R>
+where
+    T: Clone + PartialOrd + SampleUniform,
+    R: Rng,
+{
+    /// Generates a random sample within the specified bounds.
+    ///
+    /// Equal bounds are valid and always yield that single value.
+    fn try_generate(&mut self) -> Option<T> {
+        if self.lb <= self.ub {
+            Some(self.rng.gen_range(self.lb.clone()..=self.ub.clone()))
         } else {
             None
         }
     }
 }
 
+/// A biased coin generator.
+///
+/// Generates `true` with a probability of `prob` and `false` otherwise.
+pub struct Bernoulli<R = DefaultRng> {
+    prob: f64,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl Bernoulli {
+    /// Creates a new `Bernoulli` with the specified probability.
+    pub fn new(prob: f64) -> Self {
+        Self::with_rng(prob, rand::thread_rng())
+    }
+}
+
+impl<R> Bernoulli<R>
+where
+    R: Rng,
+{
+    /// Creates a new `Bernoulli` with the specified probability and RNG.
+    pub fn with_rng(prob: f64, rng: R) -> Self {
+        Self {
+            prob: clamp_prob(prob),
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set probability of generating `true`.
+    pub fn set_prob(&mut self, prob: f64) {
+        self.prob = clamp_prob(prob);
+    }
+}
+
+impl<R> Generator<bool> for Bernoulli<R>
+where
+    R: Rng,
+{
+    /// Generates a random boolean.
+    fn try_generate(&mut self) -> Option<bool> {
+        Some(self.rng.gen_bool(self.prob))
+    }
+}
+
 /// A generator that randomly samples from a collection of values.
-pub struct UniformCollection<T> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UniformCollection<T, R = DefaultRng> {
     values: Vec<T>,
-    rng: ThreadRng,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: R,
 }
 
+#[cfg(feature = "std")]
 impl<T> UniformCollection<T> {
     /// Creates a new `UniformCollection` with the given initial values.
     pub fn new(values: Vec<T>) -> Self {
-        Self {
-            values,
-            rng: rand::thread_rng(),
-        }
+        Self::with_rng(values, rand::thread_rng())
+    }
+
+    /// Creates a new `UniformCollection` with clones of the given initial values.
+    pub fn from_slice(values: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(values.to_vec())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> FromIterator<T> for UniformCollection<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<T, R> UniformCollection<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `UniformCollection` with the given initial values and RNG.
+    pub fn with_rng(values: Vec<T>, rng: R) -> Self {
+        Self { values, rng }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
     }
 
     /// Check if the collection is empty.
     pub fn is_empty(&self) -> bool {
         self.values.is_empty()
     }
+
+    /// Get the number of values in the collection.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Add a value to the collection.
+    pub fn push(&mut self, value: T) {
+        self.values.push(value);
+    }
+
+    /// Add multiple values to the collection.
+    pub fn extend(&mut self, values: impl IntoIterator<Item = T>) {
+        self.values.extend(values);
+    }
+
+    /// Remove all values from the collection.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Get the value at `index`, or `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.values.get(index)
+    }
+
+    /// Remove and return the value at `index`, shifting the following values down, or
+    /// return `None` if out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        (index < self.values.len()).then(|| self.values.remove(index))
+    }
+
+    /// Keep only the values for which `f` returns `true`, preserving their order.
+    pub fn retain(&mut self, f: impl FnMut(&T) -> bool) {
+        self.values.retain(f);
+    }
+
+    /// Sample `k` values at distinct positions of the collection, in random order, without
+    /// modifying it.
+    ///
+    /// Runs a partial Fisher-Yates shuffle of the first `k` positions and then undoes its
+    /// swaps, so it takes `O(k)` time and never copies or shuffles the whole collection.
+    /// Returns `None` if `k` exceeds the number of values.
+    pub fn sample_n(&mut self, k: usize) -> Option<Vec<T>>
+    where
+        T: Clone,
+    {
+        let len = self.values.len();
+        if k > len {
+            return None;
+        }
+        let swaps: Vec<usize> = (0..k)
+            .map(|i| {
+                let j = self.rng.gen_range(i..len);
+                self.values.swap(i, j);
+                j
+            })
+            .collect();
+        let sampled = self.values[..k].to_vec();
+        // Undo the swaps in reverse order to restore the original order
+        for (i, &j) in swaps.iter().enumerate().rev() {
+            self.values.swap(i, j);
+        }
+        Some(sampled)
+    }
 }
 
-impl<T> Generator<T> for UniformCollection<T>
+impl<T, R> Generator<T> for UniformCollection<T, R>
 where
     T: Clone,
+    R: Rng,
 {
     /// Generates a random sample from the resource.
     fn try_generate(&mut self) -> Option<T> {
@@ -78,134 +436,3221 @@ where
     }
 }
 
-/// A switch generator that randomly selects between two generators.
-pub struct RandomSwitch<G1, G2> {
-    gen1: G1,
-    gen2: G2,
-    prob: f64,
-    rng: ThreadRng,
+/// A generator that randomly samples from a collection of values without replacement.
+///
+/// Each value is generated at most once, after which the generator returns `None`
+/// until it is [`reset`](UniformCollectionNoReplace::reset).
+pub struct UniformCollectionNoReplace<T, R = DefaultRng> {
+    values: Vec<T>,
+    remaining: usize,
+    rng: R,
 }
 
-impl<G1, G2> RandomSwitch<G1, G2> {
-    /// Creates a new `RandomSwitch` with the specified generators.
-    pub fn new(gen1: G1, gen2: G2, prob: f64) -> Self {
-        let mut g = Self {
-            gen1,
-            gen2,
-            prob,
-            rng: rand::thread_rng(),
-        };
-        g.set_g1_prob(prob);
-        g
+#[cfg(feature = "std")]
+impl<T> UniformCollectionNoReplace<T> {
+    /// Creates a new `UniformCollectionNoReplace` with the given values.
+    pub fn new(values: Vec<T>) -> Self {
+        Self::with_rng(values, rand::thread_rng())
     }
+}
 
-    /// Set probability of selecting the first generator.
-    pub fn set_g1_prob(&mut self, prob: f64) {
-        if prob < 0.0 {
-            self.prob = 0.0;
-        } else if prob > 1.0 {
-            self.prob = 1.0;
-        } else {
-            self.prob = prob;
+impl<T, R> UniformCollectionNoReplace<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `UniformCollectionNoReplace` with the given values and RNG.
+    pub fn with_rng(values: Vec<T>, rng: R) -> Self {
+        Self {
+            remaining: values.len(),
+            values,
+            rng,
         }
     }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Get the number of values not yet generated.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Check if all values have been generated.
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Restore all values so that they can be generated again.
+    pub fn reset(&mut self) {
+        self.remaining = self.values.len();
+    }
 }
 
-impl<T, G1, G2> Generator<T> for RandomSwitch<G1, G2>
+impl<T, R> Generator<T> for UniformCollectionNoReplace<T, R>
 where
-    G1: Generator<T>,
-    G2: Generator<T>,
+    T: Clone,
+    R: Rng,
 {
-    /// Generates a random sample from one of the generators.
+    /// Generates a random sample from the values not yet generated.
     fn try_generate(&mut self) -> Option<T> {
-        if self.rng.gen_bool(self.prob) {
-            self.gen1.try_generate()
+        if self.remaining == 0 {
+            None
         } else {
-            self.gen2.try_generate()
+            // Move the selected value behind the remaining ones
+            let index = self.rng.gen_range(0..self.remaining);
+            self.remaining -= 1;
+            self.values.swap(index, self.remaining);
+            Some(self.values[self.remaining].clone())
         }
     }
 }
 
-/// A generator that randomly chooses between a constant value and another generator.
-pub type SwitchConstant<T, G> = RandomSwitch<Constant<T>, G>;
-
-/// A generator that randomly generates a "flags" type value.
+/// A generator that randomly samples from a collection of weighted values.
 ///
-/// The generator will randomly select a flag from the flag set with a probability of `prob`.
-pub struct RandomFlags<T> {
-    rng: ThreadRng,
-    prob: f64,
-    inclusion: T,
-    exclusion: T,
-    constraints: Vec<(T, T)>,
+/// Each value is selected with a probability proportional to its weight.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "WeightedEntries<T>",
+        bound(deserialize = "T: Deserialize<'de>, R: Default")
+    )
+)]
+pub struct WeightedCollection<T, R = DefaultRng> {
+    values: Vec<T>,
+    weights: Vec<f64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    dist: Option<WeightedIndex<f64>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: R,
 }
 
-impl<T> RandomFlags<T>
+/// The serialized fields of a [`WeightedCollection`], checked before deserializing it.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct WeightedEntries<T> {
+    values: Vec<T>,
+    weights: Vec<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl<T, R> TryFrom<WeightedEntries<T>> for WeightedCollection<T, R>
 where
-    T: Flags,
+    R: Default,
 {
-    /// Creates a new `RandomFlag` with the specific flag type.
-    pub fn new(prob: f64) -> Self {
-        Self {
-            rng: rand::thread_rng(),
-            prob,
-            inclusion: T::empty(),
-            exclusion: T::empty(),
-            constraints: Vec::new(),
+    type Error = &'static str;
+
+    fn try_from(entries: WeightedEntries<T>) -> Result<Self, Self::Error> {
+        if entries.values.len() != entries.weights.len() {
+            return Err("values and weights must have the same length");
+        }
+        if entries
+            .weights
+            .iter()
+            .any(|weight| weight.is_nan() || *weight < 0.0)
+        {
+            return Err("weights must be non-negative");
         }
+        Ok(Self {
+            values: entries.values,
+            weights: entries.weights,
+            dist: None,
+            rng: R::default(),
+        })
     }
+}
 
-    /// Set probability of selecting a flag.
-    pub fn set_prob(&mut self, prob: f64) {
-        if prob < 0.0 {
-            self.prob = 0.0;
-        } else if prob > 1.0 {
-            self.prob = 1.0;
-        } else {
-            self.prob = prob;
+#[cfg(feature = "std")]
+impl<T> WeightedCollection<T> {
+    /// Creates a new `WeightedCollection` with the given `(value, weight)` pairs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any weight is negative.
+    pub fn new(entries: Vec<(T, f64)>) -> Self {
+        Self::with_rng(entries, rand::thread_rng())
+    }
+}
+
+impl<T, R> WeightedCollection<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `WeightedCollection` with the given `(value, weight)` pairs and RNG.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any weight is negative.
+    pub fn with_rng(entries: Vec<(T, f64)>, rng: R) -> Self {
+        let mut g = Self {
+            values: Vec::with_capacity(entries.len()),
+            weights: Vec::with_capacity(entries.len()),
+            dist: None,
+            rng,
+        };
+        for (value, weight) in entries {
+            g.push(value, weight);
         }
+        g
     }
 
-    /// Include some flags in the generator. Value generated will always include these flags.
-    pub fn include(&mut self, flags: T) {
-        self.inclusion = T::from_bits_truncate(self.inclusion.bits() | flags.bits());
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
     }
 
-    /// Exclude some flags from the generator. Value generated will never include these flags.
-    pub fn exclude(&mut self, flags: T) {
-        self.exclusion = T::from_bits_truncate(self.exclusion.bits() | flags.bits());
+    /// Add a value with the given weight to the collection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is negative.
+    pub fn push(&mut self, value: T, weight: f64) {
+        assert!(weight >= 0.0, "Weight must be non-negative");
+        self.values.push(value);
+        self.weights.push(weight);
+        self.dist = None;
     }
 
-    /// Add a constraint to the generator.
+    /// Get the weights of the values, in insertion order.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Get the probability of sampling each value, in insertion order.
     ///
-    /// If `flag1` is selected, then `flag2` must also be selected.
-    pub fn constraint(&mut self, flag1: T, flag2: T) {
-        self.constraints.push((flag1, flag2));
+    /// Returns `None` if the collection is empty or all weights are zero, in which case
+    /// generation always fails.
+    pub fn normalized_weights(&self) -> Option<Vec<f64>> {
+        normalize_weights(self.weights.iter().copied())
+    }
+
+    /// Get the probability of sampling the value at `index`.
+    ///
+    /// Returns `None` if `index` is out of bounds or all weights are zero.
+    pub fn probability_of(&self, index: usize) -> Option<f64> {
+        let total: f64 = self.weights.iter().sum();
+        let weight = self.weights.get(index)?;
+        (total > 0.0).then(|| weight / total)
+    }
+
+    /// Set the weight of the value at `index`.
+    ///
+    /// The sampling distribution is rebuilt on the next generation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is negative or `index` is out of bounds.
+    pub fn set_weight(&mut self, index: usize, weight: f64) {
+        assert!(weight >= 0.0, "Weight must be non-negative");
+        self.weights[index] = weight;
+        self.dist = None;
+    }
+
+    /// Check if the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
     }
 }
 
-impl<T> Generator<T> for RandomFlags<T>
+impl<T, R> Generator<T> for WeightedCollection<T, R>
 where
-    T: Flags + Debug,
+    T: Clone,
+    R: Rng,
 {
-    /// Generates a random flag value.
+    /// Generates a weighted random sample from the collection.
+    ///
+    /// Returns `None` if the collection is empty or all weights are zero.
     fn try_generate(&mut self) -> Option<T> {
-        let mut value = T::Bits::EMPTY;
-        for flag in T::FLAGS.iter() {
-            if self.rng.gen_bool(self.prob) {
-                value = value | flag.value().bits();
-            }
-        }
-        // Check constraints
-        for (flag1, flag2) in self.constraints.iter() {
-            if (value | flag1.bits()) == value {
-                value = value | flag2.bits();
-            }
+        if self.dist.is_none() {
+            self.dist = WeightedIndex::new(&self.weights).ok();
+        }
+        let index = self.dist.as_ref()?.sample(&mut self.rng);
+        self.values.get(index).cloned()
+    }
+}
+
+/// A switch generator that randomly selects between two generators.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RandomSwitch<G1, G2, R = DefaultRng> {
+    gen1: G1,
+    gen2: G2,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_prob"))]
+    prob: f64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_choice: Option<bool>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<G1, G2> RandomSwitch<G1, G2> {
+    /// Creates a new `RandomSwitch` with the specified generators.
+    pub fn new(gen1: G1, gen2: G2, prob: f64) -> Self {
+        Self::with_rng(gen1, gen2, prob, rand::thread_rng())
+    }
+}
+
+impl<G1, G2, R> RandomSwitch<G1, G2, R>
+where
+    R: Rng,
+{
+    /// Creates a new `RandomSwitch` with the specified generators and RNG.
+    pub fn with_rng(gen1: G1, gen2: G2, prob: f64, rng: R) -> Self {
+        Self {
+            gen1,
+            gen2,
+            prob: clamp_prob(prob),
+            last_choice: None,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set probability of selecting the first generator.
+    ///
+    /// The probability is clamped into `[0.0, 1.0]`, so a probability of `0.0` always
+    /// selects the second generator and `1.0` always selects the first one.
+    pub fn set_g1_prob(&mut self, prob: f64) {
+        self.prob = clamp_prob(prob);
+    }
+
+    /// Get probability of selecting the first generator.
+    pub fn g1_prob(&self) -> f64 {
+        self.prob
+    }
+
+    /// Get whether the most recent generation selected the first generator.
+    ///
+    /// Returns `None` if no value has been generated yet.
+    pub fn last_choice(&self) -> Option<bool> {
+        self.last_choice
+    }
+}
+
+impl<T, G1, G2, R> Generator<T> for RandomSwitch<G1, G2, R>
+where
+    G1: Generator<T>,
+    G2: Generator<T>,
+    R: Rng,
+{
+    /// Generates a random sample from one of the generators.
+    fn try_generate(&mut self) -> Option<T> {
+        let choice = self.rng.gen_bool(self.prob);
+        self.last_choice = Some(choice);
+        if choice {
+            self.gen1.try_generate()
+        } else {
+            self.gen2.try_generate()
+        }
+    }
+}
+
+impl<G1, G2, R> Debug for RandomSwitch<G1, G2, R>
+where
+    G1: Debug,
+    G2: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomSwitch")
+            .field("gen1", &self.gen1)
+            .field("gen2", &self.gen2)
+            .field("prob", &self.prob)
+            .field("last_choice", &self.last_choice)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A generator that randomly chooses between a constant value and another generator.
+pub type SwitchConstant<T, G> = RandomSwitch<Constant<T>, G>;
+
+/// A generator that randomly selects among many generators.
+///
+/// Each call picks one generator with a probability proportional to its weight
+/// and delegates to it. Generators added with [`RandomChoice::push`] have a weight
+/// of `1.0`, so they are chosen uniformly unless other weights are given.
+pub struct RandomChoice<T, R = DefaultRng> {
+    generators: Vec<Box<dyn Generator<T>>>,
+    weights: Vec<f64>,
+    dist: Option<WeightedIndex<f64>>,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> RandomChoice<T> {
+    /// Creates a new empty `RandomChoice`.
+    pub fn new() -> Self {
+        Self::with_rng(rand::thread_rng())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for RandomChoice<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, R> RandomChoice<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new empty `RandomChoice` with the specified RNG.
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            generators: Vec::new(),
+            weights: Vec::new(),
+            dist: None,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Add a generator with a weight of `1.0`.
+    pub fn push<G>(&mut self, generator: G)
+    where
+        G: Generator<T> + 'static,
+    {
+        self.push_weighted(generator, 1.0);
+    }
+
+    /// Add a generator with the given weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is negative.
+    pub fn push_weighted<G>(&mut self, generator: G, weight: f64)
+    where
+        G: Generator<T> + 'static,
+    {
+        assert!(weight >= 0.0, "Weight must be non-negative");
+        self.generators.push(Box::new(generator));
+        self.weights.push(weight);
+        self.dist = None;
+    }
+
+    /// Check if there are no generators to choose from.
+    pub fn is_empty(&self) -> bool {
+        self.generators.is_empty()
+    }
+
+    /// Get the probability of choosing each generator, in insertion order.
+    ///
+    /// Returns `None` if there is no generator or all weights are zero, in which case
+    /// generation always fails.
+    pub fn normalized_weights(&self) -> Option<Vec<f64>> {
+        normalize_weights(self.weights.iter().copied())
+    }
+
+    /// Get the probability of choosing the generator at `index`.
+    ///
+    /// Returns `None` if `index` is out of bounds or all weights are zero.
+    pub fn probability_of(&self, index: usize) -> Option<f64> {
+        let total: f64 = self.weights.iter().sum();
+        let weight = self.weights.get(index)?;
+        (total > 0.0).then(|| weight / total)
+    }
+}
+
+impl<T, R> Generator<T> for RandomChoice<T, R>
+where
+    R: Rng,
+{
+    /// Generates a random sample from one of the generators.
+    ///
+    /// Returns `None` if there is no generator to choose from or the chosen generator fails.
+    fn try_generate(&mut self) -> Option<T> {
+        if self.dist.is_none() {
+            self.dist = WeightedIndex::new(&self.weights).ok();
+        }
+        let index = self.dist.as_ref()?.sample(&mut self.rng);
+        self.generators[index].try_generate()
+    }
+}
+
+/// A random string generator.
+///
+/// Generates strings whose characters are sampled uniformly from a character set, which is
+/// alphanumeric by default. The length is either fixed or drawn uniformly from an inclusive
+/// range.
+pub struct StringGenerator<R = DefaultRng> {
+    charset: Vec<char>,
+    min_len: usize,
+    max_len: usize,
+    rng: R,
+}
+
+impl StringGenerator {
+    /// ASCII letters and digits.
+    pub const ALPHANUMERIC: &'static str =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    /// ASCII letters.
+    pub const ASCII_LETTERS: &'static str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+}
+
+#[cfg(feature = "std")]
+impl StringGenerator {
+    /// Creates a new `StringGenerator` generating alphanumeric strings of length `len`.
+    pub fn new(len: usize) -> Self {
+        Self::with_rng(len, rand::thread_rng())
+    }
+}
+
+impl<R> StringGenerator<R>
+where
+    R: Rng,
+{
+    /// Creates a new `StringGenerator` generating alphanumeric strings of length `len`
+    /// with the specified RNG.
+    pub fn with_rng(len: usize, rng: R) -> Self {
+        Self {
+            charset: StringGenerator::ALPHANUMERIC.chars().collect(),
+            min_len: len,
+            max_len: len,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set the character set to sample from.
+    pub fn with_charset(mut self, charset: &str) -> Self {
+        self.charset = charset.chars().collect();
+        self
+    }
+
+    /// Set the inclusive range of the string length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn with_length_range(mut self, min: usize, max: usize) -> Self {
+        assert!(min <= max, "Minimum length must not exceed maximum length");
+        self.min_len = min;
+        self.max_len = max;
+        self
+    }
+}
+
+impl<R> Generator<String> for StringGenerator<R>
+where
+    R: Rng,
+{
+    /// Generates a random string, returning `None` if the character set is empty.
+    fn try_generate(&mut self) -> Option<String> {
+        if self.charset.is_empty() {
+            return None;
+        }
+        let len = self.rng.gen_range(self.min_len..=self.max_len);
+        Some(
+            (0..len)
+                .map(|_| self.charset[self.rng.gen_range(0..self.charset.len())])
+                .collect(),
+        )
+    }
+}
+
+/// Types that can be randomly generated from an RNG without extra configuration.
+///
+/// This trait can be derived for structs and enums with the `derive` feature.
+pub trait RandomGen: Sized {
+    /// Try generates a random value using `rng`, returning `None` if it fails.
+    fn try_random_gen<R>(rng: &mut R) -> Option<Self>
+    where
+        R: Rng + ?Sized;
+}
+
+macro_rules! impl_random_gen {
+    ($($ty:ty),*) => {
+        $(
+            impl RandomGen for $ty {
+                fn try_random_gen<R>(rng: &mut R) -> Option<Self>
+                where
+                    R: Rng + ?Sized,
+                {
+                    Some(rng.gen())
+                }
+            }
+        )*
+    };
+}
+
+impl_random_gen!(
+    bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+/// A generator of [`RandomGen`] values.
+pub struct Random<T, R = DefaultRng> {
+    rng: R,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Random<T> {
+    /// Creates a new `Random` generator.
+    pub fn new() -> Self {
+        Self::with_rng(rand::thread_rng())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for Random<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, R> Random<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `Random` generator with the specified RNG.
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            rng,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<T, R> Generator<T> for Random<T, R>
+where
+    T: RandomGen,
+    R: Rng,
+{
+    /// Generates a random value of type `T`.
+    fn try_generate(&mut self) -> Option<T> {
+        T::try_random_gen(&mut self.rng)
+    }
+}
+
+/// A generator of optional values.
+///
+/// Generates `Some` value from the wrapped generator with a probability of `prob`, and
+/// `None` otherwise. The outer `try_generate` only fails if the wrapped generator fails
+/// while a value is wanted.
+pub struct OptionGenerator<G, R = DefaultRng> {
+    generator: G,
+    prob: f64,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<G> OptionGenerator<G> {
+    /// Creates a new `OptionGenerator` with the specified generator.
+    pub fn new(generator: G, prob: f64) -> Self {
+        Self::with_rng(generator, prob, rand::thread_rng())
+    }
+}
+
+impl<G, R> OptionGenerator<G, R>
+where
+    R: Rng,
+{
+    /// Creates a new `OptionGenerator` with the specified generator and RNG.
+    pub fn with_rng(generator: G, prob: f64, rng: R) -> Self {
+        Self {
+            generator,
+            prob: clamp_prob(prob),
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set probability of generating `Some` value.
+    pub fn set_prob(&mut self, prob: f64) {
+        self.prob = clamp_prob(prob);
+    }
+}
+
+impl<T, G, R> Generator<Option<T>> for OptionGenerator<G, R>
+where
+    G: Generator<T>,
+    R: Rng,
+{
+    /// Generates a random optional value.
+    fn try_generate(&mut self) -> Option<Option<T>> {
+        if self.rng.gen_bool(self.prob) {
+            self.generator.try_generate().map(Some)
+        } else {
+            Some(None)
+        }
+    }
+}
+
+/// A generator of result values.
+///
+/// Generates `Err` from the error generator with a probability of `err_prob`, and `Ok`
+/// from the value generator otherwise. The outer `try_generate` fails if the selected
+/// generator fails.
+pub struct ResultGenerator<VG, EG, R = DefaultRng> {
+    value_gen: VG,
+    err_gen: EG,
+    err_prob: f64,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<VG, EG> ResultGenerator<VG, EG> {
+    /// Creates a new `ResultGenerator` with the specified generators.
+    pub fn new(value_gen: VG, err_gen: EG, err_prob: f64) -> Self {
+        Self::with_rng(value_gen, err_gen, err_prob, rand::thread_rng())
+    }
+}
+
+impl<VG, EG, R> ResultGenerator<VG, EG, R>
+where
+    R: Rng,
+{
+    /// Creates a new `ResultGenerator` with the specified generators and RNG.
+    pub fn with_rng(value_gen: VG, err_gen: EG, err_prob: f64, rng: R) -> Self {
+        Self {
+            value_gen,
+            err_gen,
+            err_prob: clamp_prob(err_prob),
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set probability of generating `Err` value.
+    pub fn set_err_prob(&mut self, err_prob: f64) {
+        self.err_prob = clamp_prob(err_prob);
+    }
+}
+
+impl<T, E, VG, EG, R> Generator<Result<T, E>> for ResultGenerator<VG, EG, R>
+where
+    VG: Generator<T>,
+    EG: Generator<E>,
+    R: Rng,
+{
+    /// Generates a random result value.
+    fn try_generate(&mut self) -> Option<Result<T, E>> {
+        if self.rng.gen_bool(self.err_prob) {
+            self.err_gen.try_generate().map(Err)
+        } else {
+            self.value_gen.try_generate().map(Ok)
+        }
+    }
+}
+
+/// Misconfiguration of a [`RandomFlags`] generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlagConfigError<T> {
+    /// Flags that are both included and excluded.
+    pub overlapping: T,
+    /// Excluded flags that are referenced in constraints.
+    pub excluded_in_constraints: T,
+}
+
+impl<T> Display for FlagConfigError<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "flags {:?} are both included and excluded, flags {:?} are excluded but referenced in constraints",
+            self.overlapping, self.excluded_in_constraints
+        )
+    }
+}
+
+impl<T> core::error::Error for FlagConfigError<T> where T: Debug {}
+
+/// A generator that randomly generates a "flags" type value.
+///
+/// Each flag that is neither included nor excluded, and is allowed by
+/// [`restrict_to`](Self::restrict_to), is selected randomly with a probability of `prob`.
+/// Mutually exclusive and exactly-one groups are then applied to the selected flags, and
+/// inclusions and constraints add flags to them, before exclusions remove flags. With
+/// [`at_most`](Self::at_most), flags are then dropped until few enough remain, and
+/// generation fails if they cannot be. If required, an empty value is finally replaced by
+/// a non-empty default.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RandomFlags<T, R = DefaultRng> {
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: R,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_prob"))]
+    prob: f64,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_flag_probs"))]
+    flag_probs: Vec<(T, f64)>,
+    inclusion: T,
+    exclusion: T,
+    allowed: Option<T>,
+    constraints: Vec<(T, T)>,
+    exclusive_groups: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    exactly_one_groups: Vec<T>,
+    exact_count: Option<usize>,
+    max_count: Option<usize>,
+    nonempty_default: Option<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> RandomFlags<T>
+where
+    T: Flags,
+{
+    /// Creates a new `RandomFlag` with the specific flag type.
+    pub fn new(prob: f64) -> Self {
+        Self::with_rng(prob, rand::thread_rng())
+    }
+}
+
+impl<T, R> RandomFlags<T, R>
+where
+    T: Flags,
+    R: Rng,
+{
+    /// Creates a new `RandomFlag` with the specific flag type and RNG.
+    pub fn with_rng(prob: f64, rng: R) -> Self {
+        Self {
+            rng,
+            prob: clamp_prob(prob),
+            flag_probs: Vec::new(),
+            inclusion: T::empty(),
+            exclusion: T::empty(),
+            allowed: None,
+            constraints: Vec::new(),
+            exclusive_groups: Vec::new(),
+            exactly_one_groups: Vec::new(),
+            exact_count: None,
+            max_count: None,
+            nonempty_default: None,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set probability of selecting a flag, see [`set_prob`](Self::set_prob).
+    pub fn with_prob(mut self, prob: f64) -> Self {
+        self.set_prob(prob);
+        self
+    }
+
+    /// Include some flags in the generator, see [`include`](Self::include).
+    pub fn with_include(mut self, flags: T) -> Self {
+        self.include(flags);
+        self
+    }
+
+    /// Exclude some flags from the generator, see [`exclude`](Self::exclude).
+    pub fn with_exclude(mut self, flags: T) -> Self {
+        self.exclude(flags);
+        self
+    }
+
+    /// Add a constraint to the generator, see [`constraint`](Self::constraint).
+    pub fn with_constraint(mut self, flag1: T, flag2: T) -> Self {
+        self.constraint(flag1, flag2);
+        self
+    }
+
+    /// Set probability of selecting a flag.
+    pub fn set_prob(&mut self, prob: f64) {
+        self.prob = clamp_prob(prob);
+    }
+
+    /// Set probability of selecting a specific flag, overriding the global probability.
+    pub fn set_flag_prob(&mut self, flag: T, prob: f64) {
+        let prob = clamp_prob(prob);
+        match self
+            .flag_probs
+            .iter_mut()
+            .find(|(f, _)| f.bits() == flag.bits())
+        {
+            Some(entry) => entry.1 = prob,
+            None => self.flag_probs.push((flag, prob)),
+        }
+    }
+
+    /// Get probability of selecting a specific flag.
+    pub fn flag_prob(&self, flag: &T) -> f64 {
+        self.flag_probs
+            .iter()
+            .find(|(f, _)| f.bits() == flag.bits())
+            .map_or(self.prob, |(_, prob)| *prob)
+    }
+
+    /// Include some flags in the generator. Value generated will always include these flags,
+    /// unless they are also excluded. Constraints on included flags always apply.
+    pub fn include(&mut self, flags: T) {
+        self.inclusion = T::from_bits_truncate(self.inclusion.bits() | flags.bits());
+    }
+
+    /// Exclude some flags from the generator. Value generated will never include these flags.
+    ///
+    /// Exclusion takes precedence over both inclusions and constraints. Constraints on
+    /// excluded flags never apply, even if those flags are also included or required by
+    /// another constraint.
+    pub fn exclude(&mut self, flags: T) {
+        self.exclusion = T::from_bits_truncate(self.exclusion.bits() | flags.bits());
+    }
+
+    /// Restrict random selection to the flags in `allowed`; all other flags are never
+    /// selected randomly. Repeated calls narrow the restriction to their intersection.
+    ///
+    /// Only the random selection is restricted: constraints and inclusions may still add
+    /// flags outside `allowed`, while exclusions still remove flags inside it.
+    pub fn restrict_to(&mut self, allowed: T) {
+        let bits = match self.allowed.as_ref() {
+            Some(prev) => prev.bits() & allowed.bits(),
+            None => allowed.bits(),
+        };
+        self.allowed = Some(T::from_bits_retain(bits));
+    }
+
+    /// Check if the flag is allowed by the restriction, if any.
+    fn is_allowed(&self, bits: T::Bits) -> bool {
+        self.allowed
+            .as_ref()
+            .is_none_or(|allowed| (bits & allowed.bits()) == bits)
+    }
+
+    /// Get the flags random selection picks from: those that are neither included nor
+    /// excluded, and are allowed by the restriction.
+    fn candidates(&self) -> impl Iterator<Item = &'static T> + '_ {
+        T::FLAGS.iter().map(|flag| flag.value()).filter(|flag| {
+            let bits = flag.bits();
+            bits != T::Bits::EMPTY
+                && (bits & self.exclusion.bits()) != bits
+                && (bits & self.inclusion.bits()) != bits
+                && self.is_allowed(bits)
+        })
+    }
+
+    /// Add a constraint to the generator.
+    ///
+    /// If `flag1` is selected, then `flag2` must also be selected.
+    pub fn constraint(&mut self, flag1: T, flag2: T) {
+        self.constraints.push((flag1, flag2));
+    }
+
+    /// Add a mutually exclusive group of flags to the generator.
+    ///
+    /// After random selection, at most one flag of `group` is kept, chosen randomly among
+    /// the selected ones. Groups are applied before constraints and inclusions, which may
+    /// still add other flags of the group. Exactly-one groups keep this guarantee, see
+    /// [`exactly_one_of`](Self::exactly_one_of).
+    pub fn mutually_exclusive(&mut self, group: T) {
+        self.exclusive_groups.push(group);
+    }
+
+    /// Add a group of flags of which exactly one is selected.
+    ///
+    /// After random selection and mutually exclusive groups, if a flag of `group` is
+    /// included and not excluded, it is the one kept and the other flags of the group are
+    /// dropped. Otherwise, if no flag of `group` is selected, one is picked uniformly among
+    /// those that are not excluded and are allowed by [`restrict_to`](Self::restrict_to);
+    /// if several are selected, one of them is kept randomly. Excluded flags are never
+    /// picked. Including several flags of the group keeps all of them, and constraints are
+    /// applied afterwards and may still add other flags of the group.
+    ///
+    /// A picked flag never joins another selected flag of a mutually exclusive group:
+    /// flags that would are only picked if every pickable flag would, in which case the
+    /// exactly-one group takes precedence and the other flags of those mutually exclusive
+    /// groups are dropped.
+    pub fn exactly_one_of(&mut self, group: T) {
+        self.exactly_one_groups.push(group);
+    }
+
+    /// Get the flags of `value`, other than `bits`, that share a mutually exclusive group
+    /// with `bits`, which is picked by an exactly-one group.
+    fn exclusive_with(&self, value: T::Bits, bits: T::Bits) -> T::Bits {
+        self.exclusive_groups
+            .iter()
+            .filter(|group| (bits & group.bits()) == bits)
+            .fold(T::Bits::EMPTY, |shared, group| {
+                shared | (value & group.bits() & !bits)
+            })
+    }
+
+    /// Get the included flags of `group` that are not excluded, which an exactly-one
+    /// group keeps instead of picking a flag.
+    fn included_in(&self, group: &T) -> T::Bits {
+        group.bits() & self.inclusion.bits() & !self.exclusion.bits()
+    }
+
+    /// Get the flags of `group` that an exactly-one group may pick.
+    fn pickable(&self, group: &T) -> Vec<T::Bits> {
+        self.candidates()
+            .map(|flag| flag.bits())
+            .filter(|&bits| (bits & group.bits()) == bits)
+            .collect()
+    }
+
+    /// Select exactly `k` flags instead of selecting each flag with probability `prob`.
+    ///
+    /// Included flags count towards `k`, and the rest are sampled uniformly from the flags
+    /// that are neither included nor excluded, and are allowed by
+    /// [`restrict_to`](Self::restrict_to). If `k` exceeds the number of available flags,
+    /// all of them are selected; inclusions are always satisfied even if they outnumber `k`.
+    /// Mutually exclusive groups and constraints are still applied afterwards, so the final
+    /// count may differ from `k`.
+    pub fn exactly(&mut self, k: usize) {
+        self.exact_count = Some(k);
+    }
+
+    /// Keep at most `k` flags in generated values.
+    ///
+    /// After constraints, inclusions and exclusions are applied, flags are dropped randomly
+    /// until at most `k` remain. Flags are dropped in this order of priority:
+    ///
+    /// 1. Included flags and the flags kept by [`exactly_one_of`](Self::exactly_one_of)
+    ///    groups are never dropped.
+    /// 2. Flags required by a constraint on another remaining flag are only dropped if
+    ///    every other droppable flag is required too, which happens with cyclic
+    ///    constraints; such a drop may leave that constraint unsatisfied.
+    /// 3. Any other flag is dropped first.
+    ///
+    /// If the flags that are never dropped outnumber `k`, the value cannot satisfy the
+    /// limit and generation returns `None`. The non-empty fallback is applied afterwards.
+    pub fn at_most(&mut self, k: usize) {
+        self.max_count = Some(k);
+    }
+
+    /// Get the named flags contained in `value`.
+    fn contained(value: T::Bits) -> Vec<T::Bits> {
+        T::FLAGS
+            .iter()
+            .map(|flag| flag.value().bits())
+            .filter(|&bits| bits != T::Bits::EMPTY && (value & bits) == bits)
+            .collect()
+    }
+
+    /// Get the flags of `set`, the flags contained in `value`, that [`at_most`](Self::at_most)
+    /// may drop next. Flags in `protected` are never dropped.
+    fn drop_choices(&self, value: T::Bits, set: Vec<T::Bits>, protected: T::Bits) -> Vec<T::Bits> {
+        let required = self
+            .constraints
+            .iter()
+            .filter(|(flag1, _)| (value & flag1.bits()) == flag1.bits())
+            .fold(T::Bits::EMPTY, |required, (_, flag2)| {
+                required | flag2.bits()
+            });
+        let (free, required): (Vec<T::Bits>, Vec<T::Bits>) = set
+            .into_iter()
+            .filter(|&bits| (bits & protected) != bits)
+            .partition(|&bits| (bits & required) != bits);
+        if free.is_empty() {
+            required
+        } else {
+            free
+        }
+    }
+
+    /// Drop randomly selected flags of `value` until at most `k` remain, or return `None`
+    /// if only flags in `protected` are left to drop.
+    fn drop_to(&mut self, mut value: T::Bits, k: usize, protected: T::Bits) -> Option<T::Bits> {
+        loop {
+            let set = Self::contained(value);
+            if set.len() <= k {
+                return Some(value);
+            }
+            let choices = self.drop_choices(value, set, protected);
+            if choices.is_empty() {
+                return None;
+            }
+            let dropped = choices[self.rng.gen_range(0..choices.len())];
+            value = (value & !dropped) | protected;
+        }
+    }
+
+    /// Get every value [`drop_to`](Self::drop_to) may lead to from `value`.
+    fn drop_outcomes(&self, value: T::Bits, k: usize, protected: T::Bits) -> Vec<T::Bits> {
+        let mut outcomes = Vec::new();
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![value];
+        while let Some(value) = pending.pop() {
+            if !visited.insert(self.flags_mask(value)) {
+                continue;
+            }
+            let set = Self::contained(value);
+            if set.len() <= k {
+                outcomes.push(value);
+                continue;
+            }
+            for dropped in self.drop_choices(value, set, protected) {
+                pending.push((value & !dropped) | protected);
+            }
+        }
+        outcomes
+    }
+
+    /// Substitute `default` whenever the generated value would be empty.
+    ///
+    /// Exclusions still apply to `default`, so the fallback never reintroduces an excluded
+    /// flag; if every flag of `default` is excluded, the value stays empty.
+    pub fn require_nonempty(&mut self, default: T) {
+        self.nonempty_default = Some(default);
+    }
+
+    /// Apply inclusions, constraints and exclusions to the selected flags.
+    fn apply_rules(&self, mut value: T::Bits) -> T::Bits {
+        let exclusion = self.exclusion.bits();
+        // Check inclusions first, so that constraints on included flags apply, but leave
+        // excluded flags out so that they never trigger constraints
+        value = (value | self.inclusion.bits()) & !exclusion;
+        // Check constraints, repeating until no constraint adds new flags so that
+        // chained constraints are resolved. Every pass that changes `value`
+        // satisfies at least one more constraint, which bounds the passes.
+        for _ in 0..self.constraints.len() {
+            let prev = value;
+            for (flag1, flag2) in self.constraints.iter() {
+                if (value | flag1.bits()) == value {
+                    value = value | (flag2.bits() & !exclusion);
+                }
+            }
+            if value == prev {
+                break;
+            }
+        }
+        value
+    }
+
+    /// Enumerate every distinct value that random selection could lead to.
+    ///
+    /// Each subset of the selectable flags (those neither included nor excluded, and
+    /// allowed by [`restrict_to`](Self::restrict_to)) that keeps at most one flag of each
+    /// mutually exclusive group, and exactly one flag of each exactly-one group, goes
+    /// through inclusions, constraints, exclusions and the non-empty fallback, and each
+    /// resulting value is yielded once. Probabilities and [`exactly`](Self::exactly) are
+    /// ignored. With [`at_most`](Self::at_most), every value the drops may lead to is
+    /// yielded, and subsets that cannot satisfy the limit yield nothing.
+    ///
+    /// The iterator visits `2^k` subsets for `k` selectable flags, so keep `k` small. It
+    /// remembers the values yielded so far to skip duplicates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are 64 or more selectable flags, or more than 128 named flags.
+    pub fn all_combinations(&self) -> impl Iterator<Item = T> + '_ {
+        let candidates: Vec<T::Bits> = self.candidates().map(|flag| flag.bits()).collect();
+        assert!(candidates.len() < 64, "Too many selectable flags");
+        assert!(T::FLAGS.len() <= 128, "Too many named flags");
+        let fallback = self
+            .nonempty_default
+            .as_ref()
+            .map(|default| default.bits() & !self.exclusion.bits());
+        let included = self.inclusion.bits() & !self.exclusion.bits();
+        let mut yielded = BTreeSet::new();
+        (0..1u64 << candidates.len())
+            .flat_map(move |mask| {
+                let Some((value, kept)) = self.combination_of(&candidates, mask) else {
+                    return Vec::new();
+                };
+                match self.max_count {
+                    Some(k) => self.drop_outcomes(value, k, included | kept),
+                    None => vec![value],
+                }
+            })
+            .map(move |value| match fallback {
+                Some(default) if value == T::Bits::EMPTY => default,
+                _ => value,
+            })
+            .filter(move |&value| yielded.insert(self.flags_mask(value)))
+            .map(T::from_bits_truncate)
+    }
+
+    /// Get the mask of the named flags whose non-excluded part is contained in `value`.
+    ///
+    /// Generated values are unions of non-excluded parts of named flags and included flags,
+    /// so this identifies them.
+    fn flags_mask(&self, value: T::Bits) -> u128 {
+        T::FLAGS
+            .iter()
+            .map(|flag| flag.value().bits() & !self.exclusion.bits())
+            .enumerate()
+            .filter(|&(_, bits)| bits != T::Bits::EMPTY && (value & bits) == bits)
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    /// Get the value the subset `mask` of the candidate flags leads to, along with the
+    /// flags of the subset kept by exactly-one groups, or `None` if the subset breaks a
+    /// mutually exclusive or exactly-one group.
+    fn combination_of(&self, candidates: &[T::Bits], mask: u64) -> Option<(T::Bits, T::Bits)> {
+        let selected_in = |group: &T| {
+            candidates
+                .iter()
+                .enumerate()
+                .filter(|&(i, &bits)| mask >> i & 1 == 1 && (bits & group.bits()) == bits)
+                .count()
+        };
+        let grouped = self
+            .exclusive_groups
+            .iter()
+            .all(|group| selected_in(group) <= 1)
+            && self.exactly_one_groups.iter().all(|group| {
+                // Included flags leave no candidate flag of the group selected
+                match selected_in(group) {
+                    0 => {
+                        self.included_in(group) != T::Bits::EMPTY || self.pickable(group).is_empty()
+                    }
+                    1 => self.included_in(group) == T::Bits::EMPTY,
+                    _ => false,
+                }
+            });
+        let selected = candidates
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| mask >> i & 1 == 1)
+            .fold(T::Bits::EMPTY, |value, (_, &bits)| value | bits);
+        let kept = candidates
+            .iter()
+            .enumerate()
+            .filter(|&(i, &bits)| {
+                mask >> i & 1 == 1
+                    && self
+                        .exactly_one_groups
+                        .iter()
+                        .any(|group| (bits & group.bits()) == bits)
+            })
+            .fold(T::Bits::EMPTY, |kept, (_, &bits)| kept | bits);
+        grouped.then(|| (self.apply_rules(selected), kept))
+    }
+
+    /// Check the configuration for contradictions.
+    ///
+    /// Returns an error naming the flags that are both included and excluded, and the
+    /// excluded flags that are referenced in constraints.
+    pub fn validate(&self) -> Result<(), FlagConfigError<T>> {
+        let overlapping = self.inclusion.bits() & self.exclusion.bits();
+        let mut constrained = T::Bits::EMPTY;
+        for (flag1, flag2) in self.constraints.iter() {
+            constrained = constrained | flag1.bits() | flag2.bits();
+        }
+        let excluded_in_constraints = constrained & self.exclusion.bits();
+        if overlapping == T::Bits::EMPTY && excluded_in_constraints == T::Bits::EMPTY {
+            Ok(())
+        } else {
+            Err(FlagConfigError {
+                overlapping: T::from_bits_retain(overlapping),
+                excluded_in_constraints: T::from_bits_retain(excluded_in_constraints),
+            })
+        }
+    }
+}
+
+impl<T, R> Generator<T> for RandomFlags<T, R>
+where
+    T: Flags + Debug,
+    R: Rng,
+{
+    /// Generates a random flag value, or `None` if it cannot keep to
+    /// [`at_most`](Self::at_most).
+    fn try_generate(&mut self) -> Option<T> {
+        let mut value = T::Bits::EMPTY;
+        let candidates: Vec<&T> = self.candidates().collect();
+        if let Some(k) = self.exact_count {
+            let included = T::FLAGS
+                .iter()
+                .map(|flag| flag.value().bits())
+                .filter(|&bits| {
+                    bits != T::Bits::EMPTY
+                        && (bits & self.exclusion.bits()) != bits
+                        && (bits & self.inclusion.bits()) == bits
+                })
+                .count();
+            let amount = k.saturating_sub(included).min(candidates.len());
+            for index in sample(&mut self.rng, candidates.len(), amount) {
+                value = value | candidates[index].bits();
+            }
+        } else {
+            for flag in candidates {
+                if self.rng.gen_bool(self.flag_prob(flag)) {
+                    value = value | flag.bits();
+                }
+            }
+        }
+        // Keep at most one flag of each mutually exclusive group
+        for group in self.exclusive_groups.iter() {
+            let selected: Vec<T::Bits> = T::FLAGS
+                .iter()
+                .map(|flag| flag.value().bits())
+                .filter(|&bits| {
+                    bits != T::Bits::EMPTY
+                        && (bits & group.bits()) == bits
+                        && (value & bits) == bits
+                })
+                .collect();
+            if selected.len() > 1 {
+                let kept = selected[self.rng.gen_range(0..selected.len())];
+                value = (value & !group.bits()) | kept;
+            }
+        }
+        // Keep exactly one flag of each exactly-one group, protecting it from `at_most`
+        let mut protected = self.inclusion.bits() & !self.exclusion.bits();
+        for group in self.exactly_one_groups.iter() {
+            if self.included_in(group) != T::Bits::EMPTY {
+                // The included flags are kept by inclusions, so drop the rest of the group
+                value = value & !group.bits();
+                continue;
+            }
+            let pickable = self.pickable(group);
+            let selected: Vec<T::Bits> = pickable
+                .iter()
+                .copied()
+                .filter(|&bits| (value & bits) == bits)
+                .collect();
+            let choices = if selected.is_empty() {
+                // Avoid joining a mutually exclusive group unless every choice does
+                let (free, conflicting): (Vec<T::Bits>, Vec<T::Bits>) = pickable
+                    .into_iter()
+                    .partition(|&bits| self.exclusive_with(value, bits) == T::Bits::EMPTY);
+                if free.is_empty() {
+                    conflicting
+                } else {
+                    free
+                }
+            } else {
+                selected
+            };
+            if !choices.is_empty() {
+                let kept = choices[self.rng.gen_range(0..choices.len())];
+                let dropped = group.bits() | self.exclusive_with(value, kept);
+                value = (value & !dropped) | kept;
+                protected = protected | kept;
+            }
+        }
+        value = self.apply_rules(value);
+        // Drop flags beyond the maximum count
+        if let Some(k) = self.max_count {
+            value = self.drop_to(value, k, protected)?;
+        }
+        // Fall back to the non-empty default
+        if let Some(default) = self.nonempty_default.as_ref() {
+            if value == T::Bits::EMPTY {
+                value = default.bits() & !self.exclusion.bits();
+            }
+        }
+        Some(T::from_bits_truncate(value))
+    }
+}
+
+impl<T, R> Debug for RandomFlags<T, R>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomFlags")
+            .field("prob", &self.prob)
+            .field("flag_probs", &self.flag_probs)
+            .field("inclusion", &self.inclusion)
+            .field("exclusion", &self.exclusion)
+            .field("allowed", &self.allowed)
+            .field("constraints", &self.constraints)
+            .field("exclusive_groups", &self.exclusive_groups)
+            .field("exactly_one_groups", &self.exactly_one_groups)
+            .field("exact_count", &self.exact_count)
+            .field("max_count", &self.max_count)
+            .field("nonempty_default", &self.nonempty_default)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, R> RandomFlags<T, R>
+where
+    T: Flags + Debug,
+    R: Rng,
+{
+    /// Generate `samples` values and get, for each named flag, the fraction of generated
+    /// values that contain it, in declaration order.
+    ///
+    /// This helps checking that probabilities and constraints lead to the intended
+    /// distribution. Create the generator with a seeded RNG through `with_rng` to make
+    /// the result reproducible. Failed generations, such as values that cannot keep to
+    /// [`at_most`](Self::at_most), are not counted. Returns `None` if no generation
+    /// succeeded, including when `samples` is zero.
+    pub fn flag_frequencies(&mut self, samples: usize) -> Option<Vec<(T, f64)>> {
+        let mut counts = vec![0usize; T::FLAGS.len()];
+        let mut generated = 0usize;
+        for value in (0..samples).filter_map(|_| self.try_generate()) {
+            generated += 1;
+            for (count, flag) in counts.iter_mut().zip(T::FLAGS.iter()) {
+                let bits = flag.value().bits();
+                if (value.bits() & bits) == bits {
+                    *count += 1;
+                }
+            }
+        }
+        if generated == 0 {
+            return None;
+        }
+        let frequencies = T::FLAGS
+            .iter()
+            .zip(counts)
+            .map(|(flag, count)| {
+                let fraction = count as f64 / generated as f64;
+                (T::from_bits_retain(flag.value().bits()), fraction)
+            })
+            .collect();
+        Some(frequencies)
+    }
+}
+
+/// A normal (Gaussian) distribution generator.
+///
+/// Optionally, generated values can be restricted to an inclusive range by resampling
+/// at most `max_attempts` times, returning `None` if no sample falls in the range.
+pub struct Normal<T, R = DefaultRng>
+where
+    T: Float,
+    StandardNormal: Distribution<T>,
+{
+    dist: Option<rand_distr::Normal<T>>,
+    bounds: Option<(T, T)>,
+    max_attempts: usize,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> Normal<T>
+where
+    T: Float,
+    StandardNormal: Distribution<T>,
+{
+    /// Creates a new `Normal` with the specified mean and standard deviation.
+    pub fn new(mean: T, std_dev: T) -> Self {
+        Self::with_rng(mean, std_dev, rand::thread_rng())
+    }
+}
+
+impl<T, R> Normal<T, R>
+where
+    T: Float,
+    StandardNormal: Distribution<T>,
+    R: Rng,
+{
+    /// Default maximum number of attempts to fall in the bounds.
+    pub const DEFAULT_MAX_ATTEMPTS: usize = 100;
+
+    /// Creates a new `Normal` with the specified mean, standard deviation and RNG.
+    pub fn with_rng(mean: T, std_dev: T, rng: R) -> Self {
+        Self {
+            dist: if std_dev < T::zero() {
+                None
+            } else {
+                rand_distr::Normal::new(mean, std_dev).ok()
+            },
+            bounds: None,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Restrict generated values to the inclusive range `[min, max]` by resampling.
+    pub fn with_bounds(mut self, min: T, max: T) -> Self {
+        self.bounds = Some((min, max));
+        self
+    }
+
+    /// Set the maximum number of attempts to fall in the bounds.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+impl<T, R> Generator<T> for Normal<T, R>
+where
+    T: Float,
+    StandardNormal: Distribution<T>,
+    R: Rng,
+{
+    /// Generates a random sample, returning `None` if the standard deviation is negative
+    /// or not finite.
+    fn try_generate(&mut self) -> Option<T> {
+        let dist = self.dist.as_ref()?;
+        match self.bounds {
+            Some((min, max)) => (0..self.max_attempts)
+                .map(|_| dist.sample(&mut self.rng))
+                .find(|value| min <= *value && *value <= max),
+            None => Some(dist.sample(&mut self.rng)),
+        }
+    }
+}
+
+/// A generator that walks a collection of values in shuffled order.
+///
+/// Each value is generated exactly once per cycle. The values are reshuffled at the
+/// start of every cycle, and the generator only returns `None` if the collection is empty.
+pub struct ShuffledCycle<T, R = DefaultRng> {
+    values: Vec<T>,
+    pos: usize,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> ShuffledCycle<T> {
+    /// Creates a new `ShuffledCycle` with the given values.
+    pub fn new(values: Vec<T>) -> Self {
+        Self::with_rng(values, rand::thread_rng())
+    }
+}
+
+impl<T, R> ShuffledCycle<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `ShuffledCycle` with the given values and RNG.
+    pub fn with_rng(mut values: Vec<T>, mut rng: R) -> Self {
+        values.shuffle(&mut rng);
+        Self {
+            values,
+            pos: 0,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<T, R> Generator<T> for ShuffledCycle<T, R>
+where
+    T: Clone,
+    R: Rng,
+{
+    /// Generates the next value of the current cycle.
+    fn try_generate(&mut self) -> Option<T> {
+        if self.values.is_empty() {
+            return None;
+        }
+        if self.pos == self.values.len() {
+            self.values.shuffle(&mut self.rng);
+            self.pos = 0;
+        }
+        self.pos += 1;
+        Some(self.values[self.pos - 1].clone())
+    }
+}
+
+/// A switch generator that selects among many weighted generators.
+///
+/// Each call picks one generator with a probability proportional to its weight,
+/// weights need not sum to one. This is a [`RandomChoice`] where every generator
+/// is added with an explicit weight.
+pub struct WeightedSwitch<T, R = DefaultRng> {
+    choice: RandomChoice<T, R>,
+}
+
+#[cfg(feature = "std")]
+impl<T> WeightedSwitch<T> {
+    /// Creates a new empty `WeightedSwitch`.
+    pub fn new() -> Self {
+        Self::with_rng(rand::thread_rng())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for WeightedSwitch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, R> WeightedSwitch<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new empty `WeightedSwitch` with the specified RNG.
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            choice: RandomChoice::with_rng(rng),
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        self.choice.rng_mut()
+    }
+
+    /// Add a generator with the given weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is negative.
+    pub fn add<G>(&mut self, generator: G, weight: f64)
+    where
+        G: Generator<T> + 'static,
+    {
+        self.choice.push_weighted(generator, weight);
+    }
+
+    /// Get the probability of selecting each generator, in insertion order.
+    ///
+    /// Returns `None` if there is no generator or all weights are zero, in which case
+    /// generation always fails.
+    pub fn normalized_weights(&self) -> Option<Vec<f64>> {
+        self.choice.normalized_weights()
+    }
+
+    /// Get the probability of selecting the generator at `index`.
+    ///
+    /// Returns `None` if `index` is out of bounds or all weights are zero.
+    pub fn probability_of(&self, index: usize) -> Option<f64> {
+        self.choice.probability_of(index)
+    }
+}
+
+impl<T, R> Generator<T> for WeightedSwitch<T, R>
+where
+    R: Rng,
+{
+    /// Generates a random sample from one of the generators.
+    ///
+    /// Returns `None` if there is no generator with a positive weight or the chosen
+    /// generator fails.
+    fn try_generate(&mut self) -> Option<T> {
+        self.choice.try_generate()
+    }
+}
+
+/// A uniform distribution character generator.
+///
+/// Generates characters from an inclusive range uniformly, skipping the surrogate
+/// range `0xD800..=0xDFFF` which contains no valid `char`.
+pub struct UniformChar<R = DefaultRng> {
+    lb: char,
+    ub: char,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl UniformChar {
+    /// Creates a new `UniformChar` with the specified inclusive bounds.
+    pub fn new(lb: char, ub: char) -> Self {
+        Self::with_rng(lb, ub, rand::thread_rng())
+    }
+}
+
+impl<R> UniformChar<R>
+where
+    R: Rng,
+{
+    /// Creates a new `UniformChar` with the specified inclusive bounds and RNG.
+    pub fn with_rng(lb: char, ub: char, rng: R) -> Self {
+        Self { lb, ub, rng }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<R> Generator<char> for UniformChar<R>
+where
+    R: Rng,
+{
+    /// Generates a random character within the specified bounds.
+    fn try_generate(&mut self) -> Option<char> {
+        const SURROGATE_START: u32 = 0xD800;
+        const SURROGATE_LEN: u32 = 0x800;
+        if self.lb > self.ub {
+            return None;
+        }
+        let (lb, mut ub) = (self.lb as u32, self.ub as u32);
+        // Both bounds are valid chars, so either none or all surrogates lie between them
+        if lb < SURROGATE_START && ub > SURROGATE_START {
+            ub -= SURROGATE_LEN;
+        }
+        let mut value = self.rng.gen_range(lb..=ub);
+        if lb < SURROGATE_START && value >= SURROGATE_START {
+            value += SURROGATE_LEN;
+        }
+        char::from_u32(value)
+    }
+}
+
+/// A random byte buffer generator.
+///
+/// Generates byte vectors whose length is drawn from a length generator, filling
+/// them in bulk from the RNG. Each buffer is allocated once with the drawn length.
+pub struct BytesGenerator<LG, R = DefaultRng> {
+    len_gen: LG,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<LG> BytesGenerator<LG> {
+    /// Creates a new `BytesGenerator` with the specified length generator.
+    pub fn new(len_gen: LG) -> Self {
+        Self::with_rng(len_gen, rand::thread_rng())
+    }
+}
+
+#[cfg(feature = "std")]
+impl BytesGenerator<Constant<usize>> {
+    /// Creates a new `BytesGenerator` generating buffers of length `len`.
+    pub fn with_fixed_length(len: usize) -> Self {
+        Self::new(Constant::new(len))
+    }
+}
+
+impl<LG, R> BytesGenerator<LG, R>
+where
+    R: Rng,
+{
+    /// Creates a new `BytesGenerator` with the specified length generator and RNG.
+    pub fn with_rng(len_gen: LG, rng: R) -> Self {
+        Self { len_gen, rng }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<LG, R> BytesGenerator<LG, R>
+where
+    LG: LenBound,
+{
+    /// Get the maximum length of generated buffers, or `None` if the length generator
+    /// cannot generate any length.
+    pub fn max_len(&self) -> Option<usize> {
+        self.len_gen.max_len()
+    }
+}
+
+impl<LG, R> Generator<Vec<u8>> for BytesGenerator<LG, R>
+where
+    LG: Generator<usize>,
+    R: Rng,
+{
+    /// Generates a random byte buffer, returning `None` if the length generator fails.
+    fn try_generate(&mut self) -> Option<Vec<u8>> {
+        let mut bytes = vec![0; self.len_gen.try_generate()?];
+        self.rng.fill_bytes(&mut bytes);
+        Some(bytes)
+    }
+}
+
+/// A uniform distribution stepped range generator.
+///
+/// Generates values of the arithmetic progression `lb, lb + step, lb + 2 * step, ...`
+/// that do not exceed the inclusive upper bound `ub`, uniformly.
+pub struct SteppedRange<T, R = DefaultRng> {
+    lb: T,
+    ub: T,
+    step: T,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> SteppedRange<T>
+where
+    T: PrimInt,
+{
+    /// Creates a new `SteppedRange` with the specified bounds and step.
+    pub fn new(lb: T, ub: T, step: T) -> Self {
+        Self::with_rng(lb, ub, step, rand::thread_rng())
+    }
+}
+
+impl<T, R> SteppedRange<T, R>
+where
+    T: PrimInt,
+    R: Rng,
+{
+    /// Creates a new `SteppedRange` with the specified bounds, step and RNG.
+    pub fn with_rng(lb: T, ub: T, step: T, rng: R) -> Self {
+        Self { lb, ub, step, rng }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<T, R> Generator<T> for SteppedRange<T, R>
+where
+    T: PrimInt + SampleUniform,
+    R: Rng,
+{
+    /// Generates a random value of the progression.
+    ///
+    /// Returns `None` if the step is not positive or the range is empty.
+    fn try_generate(&mut self) -> Option<T> {
+        if self.step <= T::zero() || self.lb > self.ub {
+            return None;
+        }
+        if let Some(diff) = self.ub.checked_sub(&self.lb) {
+            let index = self.rng.gen_range(T::zero()..=diff / self.step);
+            return Some(self.lb + index * self.step);
+        }
+        // `ub - lb` only overflows for signed types, whose difference always fits in the
+        // unsigned 128-bit domain
+        let (lb, ub, step) = (self.lb.to_i128()?, self.ub.to_i128()?, self.step.to_u128()?);
+        let diff = ub.wrapping_sub(lb) as u128;
+        let index = self.rng.gen_range(0..=diff / step);
+        T::from(lb.wrapping_add((index * step) as i128))
+    }
+}
+
+/// A uniform distribution `f64` range generator.
+///
+/// By default, values are generated from the half-open range `[lb, ub)`: `ub` itself is
+/// never generated, even though values arbitrarily close to it can be, and equal bounds
+/// generate nothing. In inclusive mode, values are generated from the closed range
+/// `[lb, ub]`, and equal bounds always generate `lb`.
+pub struct UniformRangeF64<R = DefaultRng> {
+    lb: f64,
+    ub: f64,
+    inclusive: bool,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl UniformRangeF64 {
+    /// Creates a new half-open `UniformRangeF64` with the specified bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bound is `NaN` or infinite, or if `ub - lb` overflows.
+    pub fn new(lb: f64, ub: f64) -> Self {
+        Self::with_rng(lb, ub, rand::thread_rng())
+    }
+}
+
+impl<R> UniformRangeF64<R>
+where
+    R: Rng,
+{
+    /// Creates a new half-open `UniformRangeF64` with the specified bounds and RNG.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bound is `NaN` or infinite, or if `ub - lb` overflows.
+    pub fn with_rng(lb: f64, ub: f64, rng: R) -> Self {
+        assert!(
+            lb.is_finite() && ub.is_finite(),
+            "Bounds must be finite, got [{lb}, {ub}]"
+        );
+        assert!(
+            (ub - lb).is_finite(),
+            "Bounds are too far apart, got [{lb}, {ub}]"
+        );
+        Self {
+            lb,
+            ub,
+            inclusive: false,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set whether the upper bound is included in the range.
+    pub fn with_inclusive(mut self, inclusive: bool) -> Self {
+        self.inclusive = inclusive;
+        self
+    }
+}
+
+impl<R> Generator<f64> for UniformRangeF64<R>
+where
+    R: Rng,
+{
+    /// Generates a random sample within the specified bounds, returning `None` if the
+    /// range is empty.
+    fn try_generate(&mut self) -> Option<f64> {
+        if self.inclusive && self.lb <= self.ub {
+            Some(self.rng.gen_range(self.lb..=self.ub))
+        } else if !self.inclusive && self.lb < self.ub {
+            Some(self.rng.gen_range(self.lb..self.ub))
+        } else {
+            None
+        }
+    }
+}
+
+/// A switch generator that randomly selects between two generators or nothing.
+///
+/// The first generator is selected with a probability of `p1`, the second one with a
+/// probability of `p2`, and `None` is generated with the remaining probability.
+pub struct RandomSwitchMaybe<G1, G2, R = DefaultRng> {
+    gen1: G1,
+    gen2: G2,
+    p1: f64,
+    p2: f64,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<G1, G2> RandomSwitchMaybe<G1, G2> {
+    /// Creates a new `RandomSwitchMaybe` with the specified generators.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p1 + p2` exceeds `1.0`.
+    pub fn new(gen1: G1, gen2: G2, p1: f64, p2: f64) -> Self {
+        Self::with_rng(gen1, gen2, p1, p2, rand::thread_rng())
+    }
+}
+
+impl<G1, G2, R> RandomSwitchMaybe<G1, G2, R>
+where
+    R: Rng,
+{
+    /// Creates a new `RandomSwitchMaybe` with the specified generators and RNG.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p1 + p2` exceeds `1.0`.
+    pub fn with_rng(gen1: G1, gen2: G2, p1: f64, p2: f64, rng: R) -> Self {
+        let (p1, p2) = (clamp_prob(p1), clamp_prob(p2));
+        assert!(p1 + p2 <= 1.0, "Probabilities must not sum to more than 1");
+        Self {
+            gen1,
+            gen2,
+            p1,
+            p2,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<T, G1, G2, R> Generator<T> for RandomSwitchMaybe<G1, G2, R>
+where
+    G1: Generator<T>,
+    G2: Generator<T>,
+    R: Rng,
+{
+    /// Generates a random sample from one of the generators, or `None`.
+    fn try_generate(&mut self) -> Option<T> {
+        let x: f64 = self.rng.gen();
+        if x < self.p1 {
+            self.gen1.try_generate()
+        } else if x < self.p1 + self.p2 {
+            self.gen2.try_generate()
+        } else {
+            None
+        }
+    }
+}
+
+/// A random duration generator.
+///
+/// Generates a [`Duration`] with a whole number of milliseconds, sampled uniformly
+/// from an inclusive range. Returns `None` if the range is inverted.
+pub struct DurationGenerator<R = DefaultRng> {
+    millis: UniformRangeInclusive<u64, R>,
+}
+
+#[cfg(feature = "std")]
+impl DurationGenerator {
+    /// Creates a new `DurationGenerator` with the specified bounds in milliseconds.
+    pub fn new(min_ms: u64, max_ms: u64) -> Self {
+        Self::with_rng(min_ms, max_ms, rand::thread_rng())
+    }
+}
+
+impl<R> DurationGenerator<R>
+where
+    R: Rng,
+{
+    /// Creates a new `DurationGenerator` with the specified bounds in milliseconds and RNG.
+    pub fn with_rng(min_ms: u64, max_ms: u64, rng: R) -> Self {
+        Self {
+            millis: UniformRangeInclusive::with_rng(min_ms, max_ms, rng),
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        self.millis.rng_mut()
+    }
+}
+
+impl<R> Generator<Duration> for DurationGenerator<R>
+where
+    R: Rng,
+{
+    fn try_generate(&mut self) -> Option<Duration> {
+        self.millis.try_generate().map(Duration::from_millis)
+    }
+}
+
+/// A random IP address generator.
+///
+/// Generates a uniformly random IPv4 address with a probability of `v4_prob`, and a
+/// uniformly random IPv6 address otherwise. Generation never fails.
+pub struct IpAddrGenerator<R = DefaultRng> {
+    // Chooses whether to generate IPv4; its RNG also draws the address octets
+    switch: RandomSwitch<Constant<bool>, Constant<bool>, R>,
+}
+
+#[cfg(feature = "std")]
+impl IpAddrGenerator {
+    /// Creates a new `IpAddrGenerator` with the specified probability of IPv4.
+    pub fn new(v4_prob: f64) -> Self {
+        Self::with_rng(v4_prob, rand::thread_rng())
+    }
+}
+
+impl<R> IpAddrGenerator<R>
+where
+    R: Rng,
+{
+    /// Creates a new `IpAddrGenerator` with the specified probability of IPv4 and RNG.
+    pub fn with_rng(v4_prob: f64, rng: R) -> Self {
+        Self {
+            switch: RandomSwitch::with_rng(Constant::new(true), Constant::new(false), v4_prob, rng),
+        }
+    }
+
+    /// Set probability of generating an IPv4 address, clamped into `[0.0, 1.0]`.
+    pub fn set_v4_prob(&mut self, prob: f64) {
+        self.switch.set_g1_prob(prob);
+    }
+
+    /// Get probability of generating an IPv4 address.
+    pub fn v4_prob(&self) -> f64 {
+        self.switch.g1_prob()
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        self.switch.rng_mut()
+    }
+}
+
+impl<R> Generator<IpAddr> for IpAddrGenerator<R>
+where
+    R: Rng,
+{
+    fn try_generate(&mut self) -> Option<IpAddr> {
+        let v4 = self.switch.try_generate()?;
+        let rng = self.switch.rng_mut();
+        Some(if v4 {
+            let mut octets = [0u8; 4];
+            rng.fill_bytes(&mut octets);
+            IpAddr::V4(Ipv4Addr::from(octets))
+        } else {
+            let mut octets = [0u8; 16];
+            rng.fill_bytes(&mut octets);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        })
+    }
+}
+
+/// A distinct pair generator.
+///
+/// Generates a pair of values at two different positions of a collection, chosen
+/// uniformly among all ordered pairs of positions. Returns `None` if the collection
+/// has fewer than two values. Equal values stored at different positions may still
+/// form a pair.
+pub struct DistinctPair<T, R = DefaultRng> {
+    values: Vec<T>,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> DistinctPair<T> {
+    /// Creates a new `DistinctPair` with the given values.
+    pub fn new(values: Vec<T>) -> Self {
+        Self::with_rng(values, rand::thread_rng())
+    }
+}
+
+impl<T, R> DistinctPair<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `DistinctPair` with the given values and RNG.
+    pub fn with_rng(values: Vec<T>, rng: R) -> Self {
+        Self { values, rng }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<T, R> Generator<(T, T)> for DistinctPair<T, R>
+where
+    T: Clone,
+    R: Rng,
+{
+    /// Generates a pair of values at different positions.
+    fn try_generate(&mut self) -> Option<(T, T)> {
+        let len = self.values.len();
+        if len < 2 {
+            return None;
+        }
+        let first = self.rng.gen_range(0..len);
+        // Draw from the remaining positions, skipping over the first one
+        let mut second = self.rng.gen_range(0..len - 1);
+        if second >= first {
+            second += 1;
+        }
+        Some((self.values[first].clone(), self.values[second].clone()))
+    }
+}
+
+/// A collection generator weighted by a function of the values.
+///
+/// Samples a value of the collection with a probability proportional to the weight
+/// computed from it. Weights are computed once and cached until the collection is
+/// mutated, so the weight function should be pure. Returns `None` if the collection
+/// is empty, or if the weights are all zero or any of them is negative or NaN.
+pub struct WeightedBy<T, F, R = DefaultRng> {
+    values: Vec<T>,
+    weight: F,
+    dist: Option<WeightedIndex<f64>>,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T, F> WeightedBy<T, F>
+where
+    F: Fn(&T) -> f64,
+{
+    /// Creates a new `WeightedBy` with the given values and weight function.
+    pub fn new(values: Vec<T>, weight: F) -> Self {
+        Self::with_rng(values, weight, rand::thread_rng())
+    }
+}
+
+impl<T, F, R> WeightedBy<T, F, R>
+where
+    F: Fn(&T) -> f64,
+    R: Rng,
+{
+    /// Creates a new `WeightedBy` with the given values, weight function and RNG.
+    pub fn with_rng(values: Vec<T>, weight: F, rng: R) -> Self {
+        Self {
+            values,
+            weight,
+            dist: None,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Add a value to the collection.
+    pub fn push(&mut self, value: T) {
+        self.values.push(value);
+        self.dist = None;
+    }
+
+    /// Add multiple values to the collection.
+    pub fn extend(&mut self, values: impl IntoIterator<Item = T>) {
+        self.values.extend(values);
+        self.dist = None;
+    }
+
+    /// Remove all values from the collection.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.dist = None;
+    }
+
+    /// Check if the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<T, F, R> Generator<T> for WeightedBy<T, F, R>
+where
+    T: Clone,
+    F: Fn(&T) -> f64,
+    R: Rng,
+{
+    /// Generates a weighted random sample from the collection.
+    fn try_generate(&mut self) -> Option<T> {
+        if self.dist.is_none() {
+            let weight = &self.weight;
+            self.dist = WeightedIndex::new(self.values.iter().map(weight)).ok();
+        }
+        let index = self.dist.as_ref()?.sample(&mut self.rng);
+        Some(self.values[index].clone())
+    }
+}
+
+/// A reservoir sampling generator.
+///
+/// Values fed from a stream of unknown length are sampled with algorithm R, so that
+/// at most `capacity` of them are retained and every value fed so far is equally
+/// likely to be retained. Generation picks one of the retained values uniformly, and
+/// returns `None` if none is retained.
+pub struct Reservoir<T, R = DefaultRng> {
+    samples: Vec<T>,
+    capacity: usize,
+    seen: usize,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> Reservoir<T> {
+    /// Creates a new empty `Reservoir` retaining at most `capacity` values.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_rng(capacity, rand::thread_rng())
+    }
+}
+
+impl<T, R> Reservoir<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new empty `Reservoir` retaining at most `capacity` values, with the
+    /// specified RNG.
+    pub fn with_rng(capacity: usize, rng: R) -> Self {
+        Self {
+            samples: Vec::new(),
+            capacity,
+            seen: 0,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Feed a value of the stream to the reservoir.
+    pub fn feed(&mut self, value: T) {
+        if self.samples.len() < self.capacity {
+            self.samples.push(value);
+        } else {
+            // Retain the `i`-th value with a probability of `capacity / (i + 1)`
+            let index = self.rng.gen_range(0..=self.seen);
+            if index < self.capacity {
+                self.samples[index] = value;
+            }
+        }
+        self.seen += 1;
+    }
+
+    /// Get the retained values.
+    pub fn samples(&self) -> &[T] {
+        &self.samples
+    }
+
+    /// Get the number of values fed so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+}
+
+impl<T, R> Generator<T> for Reservoir<T, R>
+where
+    T: Clone,
+    R: Rng,
+{
+    /// Generates one of the retained values uniformly.
+    fn try_generate(&mut self) -> Option<T> {
+        self.samples.choose(&mut self.rng).cloned()
+    }
+}
+
+/// A uniform integer range generator that is safe at the type extremes.
+///
+/// Wraps a [`UniformRangeInclusive`], which samples the bounds directly without
+/// computing `ub + 1` or `ub - lb`, so ranges reaching `T::MIN` or `T::MAX`, including
+/// the full range of `T`, never overflow. Its shifts saturate at the type extremes
+/// instead. Returns `None` if `lb > ub`.
+pub struct SaturatingRange<T, R = DefaultRng> {
+    range: UniformRangeInclusive<T, R>,
+}
+
+#[cfg(feature = "std")]
+impl<T> SaturatingRange<T>
+where
+    T: PrimInt,
+{
+    /// Creates a new `SaturatingRange` with the specified inclusive bounds.
+    pub fn new(lb: T, ub: T) -> Self {
+        Self::with_rng(lb, ub, rand::thread_rng())
+    }
+
+    /// Creates a new `SaturatingRange` covering every value of `T`.
+    pub fn full() -> Self {
+        Self::new(T::min_value(), T::max_value())
+    }
+}
+
+impl<T, R> SaturatingRange<T, R>
+where
+    T: PrimInt,
+    R: Rng,
+{
+    /// Creates a new `SaturatingRange` with the specified inclusive bounds and RNG.
+    pub fn with_rng(lb: T, ub: T, rng: R) -> Self {
+        Self {
+            range: UniformRangeInclusive::with_rng(lb, ub, rng),
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        self.range.rng_mut()
+    }
+
+    /// Get the lower bound of the range.
+    pub fn lb(&self) -> T {
+        self.range.lb
+    }
+
+    /// Get the upper bound of the range.
+    pub fn ub(&self) -> T {
+        self.range.ub
+    }
+
+    /// Move both bounds up by `delta`, saturating at `T::MAX`.
+    pub fn shift_up(&mut self, delta: T) {
+        self.range.lb = self.range.lb.saturating_add(delta);
+        self.range.ub = self.range.ub.saturating_add(delta);
+    }
+
+    /// Move both bounds down by `delta`, saturating at `T::MIN`.
+    pub fn shift_down(&mut self, delta: T) {
+        self.range.lb = self.range.lb.saturating_sub(delta);
+        self.range.ub = self.range.ub.saturating_sub(delta);
+    }
+}
+
+impl<T, R> Generator<T> for SaturatingRange<T, R>
+where
+    T: PrimInt + SampleUniform,
+    R: Rng,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        self.range.try_generate()
+    }
+}
+
+/// A generator failing at random.
+///
+/// Fails with a probability of `prob` without calling the wrapped generator, and
+/// delegates to it otherwise. Unlike [`OptionGenerator`], which generates `Some(None)`,
+/// the generation itself fails, which helps testing resilience to failing generators.
+pub struct Maybe<G, R = DefaultRng> {
+    generator: G,
+    prob: f64,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<G> Maybe<G> {
+    /// Creates a new `Maybe` failing with the specified probability.
+    pub fn new(generator: G, prob: f64) -> Self {
+        Self::with_rng(generator, prob, rand::thread_rng())
+    }
+}
+
+impl<G, R> Maybe<G, R>
+where
+    R: Rng,
+{
+    /// Creates a new `Maybe` failing with the specified probability, with the specified RNG.
+    pub fn with_rng(generator: G, prob: f64, rng: R) -> Self {
+        Self {
+            generator,
+            prob: clamp_prob(prob),
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Set probability of failing.
+    pub fn set_prob(&mut self, prob: f64) {
+        self.prob = clamp_prob(prob);
+    }
+}
+
+impl<T, G, R> Generator<T> for Maybe<G, R>
+where
+    G: Generator<T>,
+    R: Rng,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        if self.rng.gen_bool(self.prob) {
+            None
+        } else {
+            self.generator.try_generate()
+        }
+    }
+}
+
+/// A random permutation generator.
+///
+/// Shuffles a collection of values on construction, then generates each of them once
+/// in that order and returns `None` once exhausted. With a seeded RNG, the order is
+/// reproducible. Unlike [`ShuffledCycle`], the values are not repeated until
+/// [`reshuffle`](Self::reshuffle) is called.
+pub struct Permutation<T, R = DefaultRng> {
+    values: Vec<T>,
+    pos: usize,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl<T> Permutation<T> {
+    /// Creates a new `Permutation` of the given values.
+    pub fn new(values: Vec<T>) -> Self {
+        Self::with_rng(values, rand::thread_rng())
+    }
+}
+
+impl<T, R> Permutation<T, R>
+where
+    R: Rng,
+{
+    /// Creates a new `Permutation` of the given values with the specified RNG.
+    pub fn with_rng(mut values: Vec<T>, mut rng: R) -> Self {
+        values.shuffle(&mut rng);
+        Self {
+            values,
+            pos: 0,
+            rng,
+        }
+    }
+
+    /// Get a mutable reference to the RNG.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// Shuffle the values into a new permutation and restart from its first value.
+    pub fn reshuffle(&mut self) {
+        self.values.shuffle(&mut self.rng);
+        self.pos = 0;
+    }
+
+    /// Get the number of values left in the current permutation.
+    pub fn remaining(&self) -> usize {
+        self.values.len() - self.pos
+    }
+}
+
+impl<T, R> Generator<T> for Permutation<T, R>
+where
+    T: Clone,
+    R: Rng,
+{
+    /// Generates the next value of the permutation.
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.values.get(self.pos)?.clone();
+        self.pos += 1;
+        Some(value)
+    }
+}
+
+impl_into_iterator! {
+    [T, R] UniformRange<T, R> => T;
+    [T, R] UniformRangeInclusive<T, R> => T;
+    [R] Bernoulli<R> => bool;
+    [T, R] UniformCollection<T, R> => T;
+    [T, R] UniformCollectionNoReplace<T, R> => T;
+    [T, R] WeightedCollection<T, R> => T;
+    [T, R] RandomChoice<T, R> => T;
+    [R] StringGenerator<R> => String;
+    [T, R] Random<T, R> => T;
+    [T, R] RandomFlags<T, R> => T;
+    [T, R] Normal<T, R> => T where { T: Float, StandardNormal: Distribution<T> };
+    [T, R] ShuffledCycle<T, R> => T;
+    [T, R] WeightedSwitch<T, R> => T;
+    [R] UniformChar<R> => char;
+    [LG, R] BytesGenerator<LG, R> => Vec<u8>;
+    [T, R] SteppedRange<T, R> => T;
+    [R] UniformRangeF64<R> => f64;
+    [R] DurationGenerator<R> => Duration;
+    [R] IpAddrGenerator<R> => IpAddr;
+    [T, R] DistinctPair<T, R> => (T, T);
+    [T, F, R] WeightedBy<T, F, R> => T;
+    [T, R] Reservoir<T, R> => T;
+    [T, R] SaturatingRange<T, R> => T;
+    [T, R] Permutation<T, R> => T;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    bitflags::bitflags! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        struct Perm: u8 {
+            const A = 1;
+            const B = 1 << 1;
+            const C = 1 << 2;
+            const D = 1 << 3;
+        }
+    }
+
+    fn flags(prob: f64, seed: u64) -> RandomFlags<Perm, StdRng> {
+        RandomFlags::with_rng(prob, StdRng::seed_from_u64(seed))
+    }
+
+    #[test]
+    fn chained_constraints_are_resolved() {
+        // Declared in reverse so a single pass would miss the chain
+        let mut gen = flags(0.5, 0)
+            .with_constraint(Perm::C, Perm::D)
+            .with_constraint(Perm::B, Perm::C)
+            .with_constraint(Perm::A, Perm::B);
+        for value in gen.generate_n(1000) {
+            if value.contains(Perm::A) {
+                assert!(value.contains(Perm::B | Perm::C | Perm::D), "{value:?}");
+            }
+            if value.contains(Perm::B) {
+                assert!(value.contains(Perm::C | Perm::D), "{value:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn cyclic_constraints_terminate() {
+        let mut gen = flags(0.5, 1)
+            .with_constraint(Perm::A, Perm::B)
+            .with_constraint(Perm::B, Perm::A);
+        for value in gen.generate_n(1000) {
+            assert_eq!(value.contains(Perm::A), value.contains(Perm::B));
+        }
+    }
+
+    #[test]
+    fn exclusion_wins_over_inclusion_and_constraints() {
+        let mut gen = flags(1.0, 2)
+            .with_include(Perm::A | Perm::B)
+            .with_exclude(Perm::B | Perm::C)
+            .with_constraint(Perm::A, Perm::C);
+        for value in gen.generate_n(100) {
+            assert_eq!(value, Perm::A | Perm::D);
+        }
+        assert!(gen.validate().is_err());
+    }
+
+    #[test]
+    fn excluded_inclusions_do_not_trigger_constraints() {
+        let mut gen = flags(0.0, 2)
+            .with_include(Perm::A | Perm::C)
+            .with_exclude(Perm::A | Perm::B)
+            .with_constraint(Perm::A, Perm::D)
+            .with_constraint(Perm::C, Perm::B)
+            .with_constraint(Perm::B, Perm::D);
+        gen.restrict_to(Perm::C);
+        assert_eq!(gen.generate_n(100), [Perm::C; 100]);
+        assert_eq!(gen.all_combinations().collect::<Vec<_>>(), [Perm::C]);
+        assert_eq!(
+            gen.validate(),
+            Err(FlagConfigError {
+                overlapping: Perm::A,
+                excluded_in_constraints: Perm::A | Perm::B,
+            })
+        );
+    }
+
+    #[test]
+    fn inclusion_is_always_present() {
+        let mut gen = flags(0.0, 3).with_include(Perm::C);
+        for value in gen.generate_n(100) {
+            assert_eq!(value, Perm::C);
+        }
+        assert_eq!(gen.validate(), Ok(()));
+    }
+
+    #[test]
+    fn probabilities_are_clamped() {
+        assert_eq!(clamp_prob(1.5), 1.0);
+        assert_eq!(clamp_prob(-0.5), 0.0);
+        assert_eq!(clamp_prob(f64::NAN), 0.0);
+        assert_eq!(clamp_prob(f64::INFINITY), 1.0);
+        assert_eq!(clamp_prob(0.25), 0.25);
+    }
+
+    #[test]
+    fn out_of_range_flag_probabilities_do_not_panic() {
+        for prob in [1.5, -1.0, f64::NAN] {
+            let mut gen = flags(prob, 4);
+            gen.set_flag_prob(Perm::A, f64::NAN);
+            gen.set_flag_prob(Perm::B, 2.0);
+            assert_eq!(gen.flag_prob(&Perm::A), 0.0);
+            assert_eq!(gen.flag_prob(&Perm::B), 1.0);
+            for value in gen.generate_n(100) {
+                assert!(!value.contains(Perm::A) && value.contains(Perm::B));
+            }
+        }
+    }
+
+    #[test]
+    fn no_replace_generates_each_value_once() {
+        let mut gen =
+            UniformCollectionNoReplace::with_rng(vec![1, 2, 3, 4], StdRng::seed_from_u64(5));
+        for _ in 0..3 {
+            let mut values = gen.generate_n(4);
+            assert!(gen.is_empty());
+            assert_eq!(gen.try_generate(), None);
+            values.sort_unstable();
+            assert_eq!(values, [1, 2, 3, 4]);
+            gen.reset();
+            assert_eq!(gen.remaining(), 4);
+        }
+    }
+
+    #[test]
+    fn no_replace_empty_collection_fails() {
+        let mut gen =
+            UniformCollectionNoReplace::<u8, _>::with_rng(vec![], StdRng::seed_from_u64(6));
+        assert_eq!(gen.try_generate(), None);
+        gen.reset();
+        assert_eq!(gen.try_generate(), None);
+    }
+
+    #[test]
+    fn mutually_exclusive_keeps_at_most_one_flag() {
+        let mut gen = flags(1.0, 7);
+        gen.mutually_exclusive(Perm::A | Perm::B | Perm::C);
+        let mut kept = [false; 3];
+        for value in gen.generate_n(300) {
+            let group = value & (Perm::A | Perm::B | Perm::C);
+            assert_eq!(group.bits().count_ones(), 1, "{value:?}");
+            assert!(value.contains(Perm::D));
+            for (kept, flag) in kept.iter_mut().zip([Perm::A, Perm::B, Perm::C]) {
+                *kept |= group == flag;
+            }
+        }
+        assert_eq!(kept, [true; 3]);
+    }
+
+    #[test]
+    fn mutually_exclusive_composes_with_inclusion() {
+        let mut gen = flags(1.0, 8).with_include(Perm::B);
+        gen.mutually_exclusive(Perm::A | Perm::B);
+        for value in gen.generate_n(100) {
+            assert!(value.contains(Perm::B));
+        }
+    }
+
+    #[test]
+    fn weighted_switch_follows_weights() {
+        let mut gen = WeightedSwitch::with_rng(StdRng::seed_from_u64(9));
+        gen.add(Constant::new(0u8), 3.0);
+        gen.add(Constant::new(1u8), 1.0);
+        gen.add(Constant::new(2u8), 0.0);
+        assert_eq!(gen.normalized_weights(), Some(vec![0.75, 0.25, 0.0]));
+        assert_eq!(gen.probability_of(3), None);
+        let ones = gen.generate_n(4000).into_iter().filter(|&v| v != 0).count();
+        assert!(ones.abs_diff(1000) < 100, "{ones}");
+        let mut empty = WeightedSwitch::<u8, _>::with_rng(StdRng::seed_from_u64(10));
+        assert_eq!(empty.try_generate(), None);
+        empty.add(Constant::new(0), 0.0);
+        assert_eq!(empty.try_generate(), None);
+    }
+
+    #[test]
+    fn switch_extreme_probabilities_are_deterministic() {
+        for (seed, prob) in [(11, 0.0), (12, -3.0), (13, f64::NAN)] {
+            let mut gen = RandomSwitch::with_rng(
+                Constant::new(1u8),
+                Constant::new(2u8),
+                prob,
+                StdRng::seed_from_u64(seed),
+            );
+            assert_eq!(gen.g1_prob(), 0.0);
+            assert_eq!(gen.generate_n(1000), [2; 1000]);
+            assert_eq!(gen.last_choice(), Some(false));
+            gen.set_g1_prob(7.0);
+            assert_eq!(gen.g1_prob(), 1.0);
+            assert_eq!(gen.generate_n(1000), [1; 1000]);
+            assert_eq!(gen.last_choice(), Some(true));
+        }
+    }
+
+    #[test]
+    fn bernoulli_extreme_probabilities_are_deterministic() {
+        let mut gen = Bernoulli::with_rng(0.0, StdRng::seed_from_u64(14));
+        assert!(gen.generate_n(1000).iter().all(|&b| !b));
+        gen.set_prob(1.0);
+        assert!(gen.generate_n(1000).iter().all(|&b| b));
+        let mut gen = Bernoulli::with_rng(1.5, StdRng::seed_from_u64(15));
+        assert!(gen.generate_n(1000).iter().all(|&b| b));
+    }
+
+    #[test]
+    fn ip_addr_families_follow_v4_prob() {
+        let mut gen = IpAddrGenerator::with_rng(0.25, StdRng::seed_from_u64(16));
+        let addrs = gen.generate_n(4000);
+        let v4 = addrs.iter().filter(|addr| addr.is_ipv4()).count();
+        assert!(v4.abs_diff(1000) < 100, "{v4}");
+        // Addresses drawn from the shared RNG are not repeated
+        let first = addrs.iter().find(|addr| addr.is_ipv6()).unwrap();
+        assert_eq!(addrs.iter().filter(|addr| *addr == first).count(), 1);
+        gen.set_v4_prob(1.0);
+        assert!(gen.generate_n(100).iter().all(IpAddr::is_ipv4));
+        let mut a = IpAddrGenerator::with_rng(0.5, StdRng::seed_from_u64(17));
+        let mut b = IpAddrGenerator::with_rng(0.5, StdRng::seed_from_u64(17));
+        assert_eq!(a.generate_n(50), b.generate_n(50));
+    }
+
+    /// Get a random subset of the flags, each flag being kept with a probability of `prob`.
+    fn random_subset(rng: &mut StdRng, prob: f64) -> Perm {
+        Perm::FLAGS
+            .iter()
+            .filter(|_| rng.gen_bool(prob))
+            .fold(Perm::empty(), |subset, flag| subset | *flag.value())
+    }
+
+    /// Get a random single flag.
+    fn random_flag(rng: &mut StdRng) -> Perm {
+        *Perm::FLAGS[rng.gen_range(0..Perm::FLAGS.len())].value()
+    }
+
+    /// Build a random configuration, with at most one group of each kind.
+    fn random_config(seed: u64) -> RandomFlags<Perm, StdRng> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut gen = flags(0.5, seed)
+            .with_include(random_subset(&mut rng, 0.2))
+            .with_exclude(random_subset(&mut rng, 0.2));
+        if rng.gen_bool(0.3) {
+            gen.restrict_to(random_subset(&mut rng, 0.7));
+        }
+        for _ in 0..rng.gen_range(0..4) {
+            gen.constraint(random_flag(&mut rng), random_flag(&mut rng));
+        }
+        if rng.gen_bool(0.5) {
+            gen.mutually_exclusive(random_subset(&mut rng, 0.5));
+        }
+        if rng.gen_bool(0.5) {
+            gen.exactly_one_of(random_subset(&mut rng, 0.5));
+        }
+        if rng.gen_bool(0.3) {
+            gen.at_most(rng.gen_range(0..4));
+        }
+        if rng.gen_bool(0.3) {
+            gen.require_nonempty(random_flag(&mut rng));
+        }
+        gen
+    }
+
+    /// Check that `gen` generates exactly the values it enumerates.
+    fn assert_enumerates_samples(mut gen: RandomFlags<Perm, StdRng>) {
+        let mut enumerated: Vec<u8> = gen.all_combinations().map(|v| v.bits()).collect();
+        enumerated.sort_unstable();
+        let len = enumerated.len();
+        enumerated.dedup();
+        assert_eq!(enumerated.len(), len, "duplicates for {gen:?}");
+        let mut sampled: Vec<u8> = (0..2000)
+            .filter_map(|_| gen.try_generate())
+            .map(|v| v.bits())
+            .collect();
+        sampled.sort_unstable();
+        sampled.dedup();
+        assert_eq!(enumerated, sampled, "{gen:?}");
+    }
+
+    #[test]
+    fn all_combinations_matches_sampling() {
+        for seed in 0..300 {
+            assert_enumerates_samples(random_config(seed));
+        }
+    }
+
+    #[test]
+    fn at_most_keeps_exactly_one_flags() {
+        for seed in 0..200 {
+            let mut gen = flags(1.0, seed).with_constraint(Perm::A, Perm::C);
+            gen.exactly_one_of(Perm::A | Perm::B);
+            gen.at_most(1);
+            for value in gen.generate_n(20) {
+                assert_eq!((value & (Perm::A | Perm::B)).bits().count_ones(), 1);
+                assert!(value.bits().count_ones() <= 1, "{value:?}");
+            }
+            assert_enumerates_samples(gen);
+        }
+    }
+
+    #[test]
+    fn at_most_fails_when_protected_flags_outnumber_it() {
+        for seed in 0..200 {
+            let mut gen = flags(0.5, seed).with_include(Perm::A | Perm::B);
+            gen.exactly_one_of(Perm::C | Perm::D);
+            gen.at_most(2);
+            assert_eq!(gen.try_generate(), None);
+            assert_eq!(gen.all_combinations().count(), 0);
+        }
+        let mut gen = flags(0.5, 20).with_include(Perm::A);
+        gen.at_most(0);
+        assert_eq!(gen.try_generate(), None);
+    }
+
+    #[test]
+    fn excluded_flags_do_not_trigger_constraints() {
+        let mut gen = flags(0.5, 18)
+            .with_exclude(Perm::A)
+            .with_constraint(Perm::A, Perm::B);
+        gen.restrict_to(Perm::A | Perm::C);
+        let mut values: Vec<Perm> = gen.all_combinations().collect();
+        values.sort_unstable_by_key(Perm::bits);
+        assert_eq!(values, [Perm::empty(), Perm::C]);
+        assert_enumerates_samples(gen);
+    }
+
+    #[test]
+    fn exactly_one_avoids_mutually_exclusive_flags() {
+        let mut gen = flags(0.0, 19);
+        gen.set_flag_prob(Perm::A, 1.0);
+        gen.mutually_exclusive(Perm::A | Perm::B);
+        gen.exactly_one_of(Perm::B | Perm::C);
+        assert_eq!(gen.generate_n(100), [Perm::A | Perm::C; 100]);
+        assert!(gen.all_combinations().all(|v| v != Perm::A | Perm::B));
+    }
+
+    #[test]
+    fn exactly_one_overrides_mutually_exclusive_groups() {
+        let mut gen = flags(0.0, 20);
+        gen.set_flag_prob(Perm::A, 1.0);
+        gen.mutually_exclusive(Perm::A | Perm::B);
+        gen.exactly_one_of(Perm::B);
+        assert_eq!(gen.generate_n(100), [Perm::B; 100]);
+        assert!(gen.all_combinations().any(|v| v == Perm::B));
+    }
+
+    #[test]
+    fn exactly_one_keeps_included_flags() {
+        for prob in [0.0, 0.5, 1.0] {
+            let mut gen = flags(prob, 21).with_include(Perm::A);
+            gen.exactly_one_of(Perm::A | Perm::B);
+            for value in gen.generate_n(200) {
+                assert!(
+                    value.contains(Perm::A) && !value.contains(Perm::B),
+                    "{value:?}"
+                );
+            }
+            assert!(gen.all_combinations().all(|v| !v.contains(Perm::B)));
+            if prob == 0.5 {
+                assert_enumerates_samples(gen);
+            }
+        }
+        let mut gen = flags(1.0, 22).with_include(Perm::A | Perm::B);
+        gen.exactly_one_of(Perm::A | Perm::B | Perm::C);
+        assert_eq!(gen.generate_n(50), [Perm::A | Perm::B | Perm::D; 50]);
+    }
+
+    #[test]
+    fn flag_frequencies_count_successful_generations() {
+        // Keeping A and C from the overlapping groups cannot fit in one flag
+        let mut gen = flags(1.0, 23);
+        gen.exactly_one_of(Perm::A | Perm::B);
+        gen.exactly_one_of(Perm::B | Perm::C);
+        gen.at_most(1);
+        let failures = (0..1000).filter(|_| gen.try_generate().is_none()).count();
+        assert!((100..900).contains(&failures), "{failures}");
+        let frequencies = gen.flag_frequencies(1000).unwrap();
+        assert_eq!(frequencies[1].0, Perm::B);
+        // Every value that fits holds exactly B
+        assert_eq!(frequencies.iter().map(|&(_, f)| f).sum::<f64>(), 1.0);
+        assert_eq!(frequencies[1].1, 1.0);
+        assert_eq!(gen.flag_frequencies(0), None);
+
+        // Two included flags never fit in one
+        let mut gen = flags(0.5, 24).with_include(Perm::A | Perm::B);
+        gen.at_most(1);
+        assert_eq!(gen.flag_frequencies(100), None);
+    }
+
+    /// Get the chi-square statistic of `counts` against a uniform expectation.
+    fn chi_square(counts: &[usize]) -> f64 {
+        let expected = counts.iter().sum::<usize>() as f64 / counts.len() as f64;
+        counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    fn reservoir_retains_values_uniformly() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut counts = [0; 10];
+        for _ in 0..20000 {
+            let mut reservoir = Reservoir::with_rng(3, &mut rng);
+            for value in 0..10 {
+                reservoir.feed(value);
+            }
+            assert_eq!(reservoir.samples().len(), 3);
+            for &value in reservoir.samples() {
+                counts[value] += 1;
+            }
+        }
+        // 27.9 is the 0.001 critical value for 9 degrees of freedom
+        assert!(chi_square(&counts) < 27.9, "{counts:?}");
+    }
+
+    #[test]
+    fn no_replace_orders_values_uniformly() {
+        let mut gen =
+            UniformCollectionNoReplace::with_rng(vec![0, 1, 2, 3], StdRng::seed_from_u64(22));
+        let mut counts = [[0; 4]; 4];
+        for _ in 0..8000 {
+            for (pos, value) in gen.generate_n(4).into_iter().enumerate() {
+                counts[pos][value] += 1;
+            }
+            gen.reset();
+        }
+        // Each position holds each value equally often; 16.3 is the 0.001 critical value
+        // for 3 degrees of freedom
+        for counts in counts {
+            assert!(chi_square(&counts) < 16.3, "{counts:?}");
+        }
+    }
+
+    /// Generate `n` values and get the distinct ones, sorted.
+    fn distinct<T: Ord>(gen: &mut impl Generator<T>, n: usize) -> Vec<T> {
+        let mut values = gen.generate_n(n);
+        values.sort_unstable();
+        values.dedup();
+        values
+    }
+
+    #[test]
+    fn saturating_range_covers_full_types() {
+        let mut gen = SaturatingRange::with_rng(u8::MIN, u8::MAX, StdRng::seed_from_u64(23));
+        assert_eq!(distinct(&mut gen, 10000).len(), 256);
+        let mut gen = SaturatingRange::with_rng(i8::MIN, i8::MAX, StdRng::seed_from_u64(24));
+        assert_eq!(distinct(&mut gen, 10000).len(), 256);
+        let mut gen = SaturatingRange::with_rng(u64::MAX - 2, u64::MAX, StdRng::seed_from_u64(25));
+        assert_eq!(
+            distinct(&mut gen, 100),
+            [u64::MAX - 2, u64::MAX - 1, u64::MAX]
+        );
+    }
+
+    #[test]
+    fn saturating_range_shifts_saturate() {
+        let mut gen = SaturatingRange::with_rng(250u8, 254, StdRng::seed_from_u64(26));
+        gen.shift_up(10);
+        assert_eq!((gen.lb(), gen.ub()), (u8::MAX, u8::MAX));
+        assert_eq!(gen.generate_n(10), [u8::MAX; 10]);
+        let mut gen = SaturatingRange::with_rng(-120i8, -100, StdRng::seed_from_u64(27));
+        gen.shift_down(20);
+        assert_eq!((gen.lb(), gen.ub()), (i8::MIN, -120));
+        assert!(gen
+            .generate_n(100)
+            .iter()
+            .all(|v| (i8::MIN..=-120).contains(v)));
+        let mut gen = SaturatingRange::with_rng(1u64, 0, StdRng::seed_from_u64(28));
+        assert_eq!(gen.try_generate(), None);
+    }
+
+    #[test]
+    fn stepped_range_reaches_type_extremes() {
+        let mut gen = SteppedRange::with_rng(i8::MIN, i8::MAX, 85, StdRng::seed_from_u64(29));
+        assert_eq!(distinct(&mut gen, 200), [-128, -43, 42, 127]);
+        let mut gen = SteppedRange::with_rng(u8::MIN, u8::MAX, 51, StdRng::seed_from_u64(30));
+        assert_eq!(distinct(&mut gen, 200), [0, 51, 102, 153, 204, 255]);
+        let mut gen = SteppedRange::with_rng(u64::MAX - 10, u64::MAX, 5, StdRng::seed_from_u64(31));
+        assert_eq!(
+            distinct(&mut gen, 200),
+            [u64::MAX - 10, u64::MAX - 5, u64::MAX]
+        );
+        let mut gen = SteppedRange::with_rng(i8::MIN, i8::MAX, i8::MAX, StdRng::seed_from_u64(32));
+        assert_eq!(distinct(&mut gen, 200), [-128, -1, 126]);
+    }
+
+    #[test]
+    fn stepped_range_rejects_invalid_configurations() {
+        let mut gen = SteppedRange::with_rng(0u8, 10, 0, StdRng::seed_from_u64(33));
+        assert_eq!(gen.try_generate(), None);
+        let mut gen = SteppedRange::with_rng(0i8, 10, -1, StdRng::seed_from_u64(34));
+        assert_eq!(gen.try_generate(), None);
+        let mut gen = SteppedRange::with_rng(10u64, 0, 1, StdRng::seed_from_u64(35));
+        assert_eq!(gen.try_generate(), None);
+        let mut gen = SteppedRange::with_rng(7u8, 7, 3, StdRng::seed_from_u64(36));
+        assert_eq!(gen.generate_n(5), [7; 5]);
+    }
+
+    #[test]
+    fn float_range_bounds_follow_the_mode() {
+        let mut gen = UniformRangeF64::with_rng(-1.0, 1.0, StdRng::seed_from_u64(37));
+        assert!(gen.generate_n(1000).iter().all(|v| (-1.0..1.0).contains(v)));
+        let mut gen = UniformRangeF64::with_rng(2.5, 2.5, StdRng::seed_from_u64(38));
+        assert_eq!(gen.try_generate(), None);
+        let mut gen = gen.with_inclusive(true);
+        assert_eq!(gen.generate_n(3), [2.5; 3]);
+        let mut gen =
+            UniformRangeF64::with_rng(3.0, 2.0, StdRng::seed_from_u64(39)).with_inclusive(true);
+        assert_eq!(gen.try_generate(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bounds must be finite")]
+    fn float_range_rejects_nan_bounds() {
+        UniformRangeF64::with_rng(f64::NAN, 1.0, StdRng::seed_from_u64(40));
+    }
+
+    #[test]
+    #[should_panic(expected = "Bounds are too far apart")]
+    fn float_range_rejects_overflowing_width() {
+        UniformRangeF64::with_rng(f64::MIN, f64::MAX, StdRng::seed_from_u64(41));
+    }
+
+    #[test]
+    fn uniform_ranges_cover_128_bit_extremes() {
+        let mut gen = UniformRange::with_rng(u128::MAX - 3, u128::MAX, StdRng::seed_from_u64(40));
+        assert_eq!(
+            distinct(&mut gen, 100),
+            [u128::MAX - 3, u128::MAX - 2, u128::MAX - 1]
+        );
+        let mut gen = UniformRange::with_rng(i128::MIN, i128::MAX, StdRng::seed_from_u64(41));
+        let values = gen.generate_n(100);
+        assert!(values.iter().any(|&v| v < 0) && values.iter().any(|&v| v > 0));
+        let mut gen = UniformRange::with_rng(usize::MAX - 1, usize::MAX, StdRng::seed_from_u64(42));
+        assert_eq!(distinct(&mut gen, 10), [usize::MAX - 1]);
+
+        let mut gen =
+            UniformRangeInclusive::with_rng(u128::MAX - 2, u128::MAX, StdRng::seed_from_u64(43));
+        assert_eq!(
+            distinct(&mut gen, 100),
+            [u128::MAX - 2, u128::MAX - 1, u128::MAX]
+        );
+        let mut gen =
+            UniformRangeInclusive::with_rng(i128::MIN, i128::MAX, StdRng::seed_from_u64(44));
+        let values = gen.generate_n(100);
+        assert!(values.iter().any(|&v| v < 0) && values.iter().any(|&v| v > 0));
+        let mut gen =
+            UniformRangeInclusive::with_rng(i128::MIN, i128::MIN + 1, StdRng::seed_from_u64(45));
+        assert_eq!(distinct(&mut gen, 100), [i128::MIN, i128::MIN + 1]);
+        let mut gen =
+            UniformRangeInclusive::with_rng(usize::MAX, usize::MAX, StdRng::seed_from_u64(46));
+        assert_eq!(distinct(&mut gen, 10), [usize::MAX]);
+    }
+
+    #[test]
+    fn stepped_range_covers_128_bit_extremes() {
+        let mut gen =
+            SteppedRange::with_rng(i128::MIN, i128::MAX, i128::MAX, StdRng::seed_from_u64(47));
+        assert_eq!(distinct(&mut gen, 200), [i128::MIN, -1, i128::MAX - 1]);
+        let step = u128::MAX / 3;
+        let mut gen = SteppedRange::with_rng(0, u128::MAX, step, StdRng::seed_from_u64(48));
+        assert_eq!(distinct(&mut gen, 200), [0, step, 2 * step, u128::MAX]);
+        let mut gen = SteppedRange::with_rng(i128::MIN, i128::MAX, 1, StdRng::seed_from_u64(49));
+        let values = gen.generate_n(100);
+        assert!(values.iter().any(|&v| v < 0) && values.iter().any(|&v| v > 0));
+        let mut gen =
+            SteppedRange::with_rng(usize::MAX - 4, usize::MAX, 2, StdRng::seed_from_u64(50));
+        assert_eq!(
+            distinct(&mut gen, 200),
+            [usize::MAX - 4, usize::MAX - 2, usize::MAX]
+        );
+    }
+
+    #[test]
+    fn sample_n_returns_distinct_positions() {
+        let values: Vec<u32> = (0..20).collect();
+        let mut gen = UniformCollection::with_rng(values.clone(), StdRng::seed_from_u64(51));
+        for k in [0, 1, 5, 20] {
+            for _ in 0..50 {
+                let mut sampled = gen.sample_n(k).unwrap();
+                assert_eq!(sampled.len(), k);
+                assert_eq!(gen.values, values);
+                sampled.sort_unstable();
+                sampled.dedup();
+                assert_eq!(sampled.len(), k);
+                assert!(sampled.iter().all(|v| values.contains(v)));
+            }
+        }
+        let mut sampled = gen.sample_n(20).unwrap();
+        sampled.sort_unstable();
+        assert_eq!(sampled, values);
+        assert_eq!(gen.values, values);
+        // Every value shows up, not only the first positions
+        let mut seen = [false; 20];
+        for _ in 0..100 {
+            for v in gen.sample_n(3).unwrap() {
+                seen[v as usize] = true;
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn sample_n_fails_beyond_len() {
+        let mut gen = UniformCollection::with_rng(vec!['a', 'b'], StdRng::seed_from_u64(52));
+        assert_eq!(gen.sample_n(3), None);
+        let mut gen = UniformCollection::with_rng(Vec::<u8>::new(), StdRng::seed_from_u64(53));
+        assert_eq!(gen.sample_n(0), Some(vec![]));
+        assert_eq!(gen.sample_n(1), None);
+    }
+
+    #[cfg(feature = "serde")]
+    mod config {
+        use super::*;
+        use rand::RngCore;
+        use serde::de::DeserializeOwned;
+
+        /// A seeded RNG that deserialized generators restore through `Default`.
+        struct TestRng(StdRng);
+
+        impl Default for TestRng {
+            fn default() -> Self {
+                Self(StdRng::seed_from_u64(42))
+            }
+        }
+
+        impl RngCore for TestRng {
+            fn next_u32(&mut self) -> u32 {
+                self.0.next_u32()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                self.0.next_u64()
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                self.0.fill_bytes(dest)
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.0.try_fill_bytes(dest)
+            }
+        }
+
+        /// Check that `gen` generates the same values after a round trip through JSON.
+        fn assert_round_trip<G, T>(mut gen: G)
+        where
+            G: Generator<T> + Serialize + DeserializeOwned,
+            T: PartialEq + Debug,
+        {
+            let json = serde_json::to_string(&gen).unwrap();
+            let mut restored: G = serde_json::from_str(&json).unwrap();
+            assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+            assert_eq!(restored.generate_n(100), gen.generate_n(100));
+        }
+
+        #[test]
+        fn uniform_range_round_trips() {
+            assert_round_trip(UniformRange::with_rng(-5i32, 17, TestRng::default()));
+        }
+
+        #[test]
+        fn weighted_collection_round_trips() {
+            let entries = vec![('a', 1.0), ('b', 0.0), ('c', 2.5)];
+            assert_round_trip(WeightedCollection::with_rng(entries, TestRng::default()));
+        }
+
+        #[test]
+        fn random_flags_round_trip() {
+            let mut gen = RandomFlags::with_rng(0.4, TestRng::default())
+                .with_include(Perm::A)
+                .with_exclude(Perm::D)
+                .with_constraint(Perm::B, Perm::C);
+            gen.set_flag_prob(Perm::C, 0.9);
+            gen.mutually_exclusive(Perm::B | Perm::D);
+            gen.at_most(3);
+            gen.require_nonempty(Perm::B);
+            assert_round_trip::<_, Perm>(gen);
+        }
+
+        #[test]
+        fn random_flags_load_configurations_without_newer_fields() {
+            let json = r#"{
+                "prob": 0.5,
+                "flag_probs": [["C", 1.0]],
+                "inclusion": "A",
+                "exclusion": "D",
+                "constraints": [["A", "B"]],
+                "exclusive_groups": [],
+                "exact_count": null
+            }"#;
+            let mut gen: RandomFlags<Perm, TestRng> = serde_json::from_str(json).unwrap();
+            for value in gen.generate_n(100) {
+                assert_eq!(value, Perm::A | Perm::B | Perm::C);
+            }
+        }
+
+        #[test]
+        fn weighted_collection_rejects_mismatched_weights() {
+            for json in [
+                r#"{"values":["a","b"],"weights":[1.0]}"#,
+                r#"{"values":["a"],"weights":[1.0,2.0]}"#,
+                r#"{"values":["a","b"],"weights":[1.0,-2.0]}"#,
+            ] {
+                assert!(serde_json::from_str::<WeightedCollection<String, TestRng>>(json).is_err());
+            }
+        }
+
+        #[test]
+        fn uniform_collection_round_trips() {
+            assert_round_trip(UniformCollection::with_rng(
+                vec![3u8, 1, 4, 1, 5],
+                TestRng::default(),
+            ));
+        }
+
+        #[test]
+        fn random_switch_round_trips() {
+            let gen1 = UniformRange::with_rng(0u32, 10, TestRng::default());
+            let gen2 = Constant::new(100u32);
+            assert_round_trip(RandomSwitch::with_rng(gen1, gen2, 0.3, TestRng::default()));
+        }
+
+        #[test]
+        fn deserialized_probabilities_are_clamped() {
+            let mut gen: RandomSwitch<Constant<u8>, Constant<u8>, TestRng> =
+                serde_json::from_str(r#"{"gen1":1,"gen2":2,"prob":2.0}"#).unwrap();
+            assert_eq!(gen.generate_n(50), [1; 50]);
         }
-        // Check exclusions
-        value = value & !self.exclusion.bits();
-        // Check inclusions
-        value = value | self.inclusion.bits();
-        Some(T::from_bits_truncate(value))
     }
 }
@@ -0,0 +1,271 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::Generator;
+use num_traits::{CheckedAdd, One};
+
+/// A counter generator.
+///
+/// Generates `start`, `start + step`, `start + 2 * step`, ... and stops once the next
+/// value would overflow `T` or exceed the optional inclusive maximum.
+pub struct Counter<T> {
+    start: T,
+    current: Option<T>,
+    step: T,
+    max: Option<T>,
+}
+
+impl<T> Counter<T>
+where
+    T: Clone + One,
+{
+    /// Create a new counter starting at `start` with a step of one.
+    pub fn new(start: T) -> Self {
+        Self::with_step(start, T::one())
+    }
+}
+
+impl<T> Counter<T>
+where
+    T: Clone,
+{
+    /// Create a new counter starting at `start` with the specified step.
+    pub fn with_step(start: T, step: T) -> Self {
+        Self {
+            current: Some(start.clone()),
+            start,
+            step,
+            max: None,
+        }
+    }
+
+    /// Set the inclusive maximum of the counter.
+    pub fn with_max(mut self, max: T) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Restart the counter from its start value.
+    pub fn reset(&mut self) {
+        self.current = Some(self.start.clone());
+    }
+}
+
+impl<T> Generator<T> for Counter<T>
+where
+    T: Clone + PartialOrd + CheckedAdd,
+{
+    /// Generates the current value and advances the counter.
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.current.take()?;
+        if self.max.as_ref().is_some_and(|max| value > *max) {
+            return None;
+        }
+        self.current = value
+            .checked_add(&self.step)
+            .filter(|next| self.max.as_ref().is_none_or(|max| next <= max));
+        Some(value)
+    }
+}
+
+/// A stateful generator driven by a closure.
+///
+/// Each generation calls the closure with a mutable reference to the state, which
+/// makes it easy to encode small state machines.
+pub struct Stateful<S, T, F> {
+    state: S,
+    f: F,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<S, T, F> Stateful<S, T, F>
+where
+    F: FnMut(&mut S) -> Option<T>,
+{
+    /// Create a new stateful generator with the initial state.
+    pub fn new(state: S, f: F) -> Self {
+        Self {
+            state,
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<S, T, F> Generator<T> for Stateful<S, T, F>
+where
+    F: FnMut(&mut S) -> Option<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        (self.f)(&mut self.state)
+    }
+}
+
+/// A generator calling a closure.
+///
+/// This adapts arbitrary logic into a [`Generator`], for example reading lines
+/// from a file:
+///
+/// ```no_run
+/// use std::io::{BufRead, BufReader};
+/// use km_gen::{FromFn, Generator};
+///
+/// let file = std::fs::File::open("inputs.txt").unwrap();
+/// let mut lines = BufReader::new(file).lines();
+/// let mut gen = FromFn::new(move || lines.next()?.ok());
+/// let line: Option<String> = gen.try_generate();
+/// ```
+pub struct FromFn<F, T> {
+    f: F,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<F, T> FromFn<F, T>
+where
+    F: FnMut() -> Option<T>,
+{
+    /// Create a new generator from a closure.
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, T> Generator<T> for FromFn<F, T>
+where
+    F: FnMut() -> Option<T>,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        (self.f)()
+    }
+}
+
+/// An ordered cycle generator.
+///
+/// Generates the values of a collection in order, wrapping around to the first one
+/// after the last. Returns `None` only if the collection is empty.
+pub struct CycleOrdered<T> {
+    values: Vec<T>,
+    pos: usize,
+}
+
+impl<T> CycleOrdered<T> {
+    /// Create a new ordered cycle generator.
+    pub fn new(values: Vec<T>) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    /// Restart the cycle from the first value.
+    pub fn reset(&mut self) {
+        self.pos = 0;
+    }
+}
+
+impl<T> Generator<T> for CycleOrdered<T>
+where
+    T: Clone,
+{
+    fn try_generate(&mut self) -> Option<T> {
+        let value = self.values.get(self.pos)?.clone();
+        self.pos = (self.pos + 1) % self.values.len();
+        Some(value)
+    }
+}
+
+/// A cartesian product generator.
+///
+/// Generates every pair `(a, b)` of a value `a` of the first collection and a value
+/// `b` of the second one exactly once, in row-major order, then returns `None`. If
+/// either collection is empty, no pair is generated.
+pub struct Product<T, U> {
+    first: Vec<T>,
+    second: Vec<U>,
+    pos: usize,
+}
+
+impl<T, U> Product<T, U> {
+    /// Create a new cartesian product generator.
+    pub fn new(first: Vec<T>, second: Vec<U>) -> Self {
+        Self {
+            first,
+            second,
+            pos: 0,
+        }
+    }
+
+    /// Restart the enumeration from the first pair.
+    pub fn reset(&mut self) {
+        self.pos = 0;
+    }
+}
+
+impl<T, U> Generator<(T, U)> for Product<T, U>
+where
+    T: Clone,
+    U: Clone,
+{
+    fn try_generate(&mut self) -> Option<(T, U)> {
+        let cols = self.second.len();
+        if cols == 0 {
+            return None;
+        }
+        let a = self.first.get(self.pos / cols)?.clone();
+        let b = self.second[self.pos % cols].clone();
+        self.pos += 1;
+        Some((a, b))
+    }
+}
+
+impl_into_iterator! {
+    [T] Counter<T> => T;
+    [S, T, F] Stateful<S, T, F> => T;
+    [F, T] FromFn<F, T> => T;
+    [T] CycleOrdered<T> => T;
+    [T, U] Product<T, U> => (T, U);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn counter_steps_up_to_max() {
+        let mut counter = Counter::with_step(1u32, 3).with_max(10);
+        assert_eq!(counter.try_generate_n(4), Some(vec![1, 4, 7, 10]));
+        assert_eq!(counter.try_generate(), None);
+        counter.reset();
+        assert_eq!(counter.try_generate(), Some(1));
+    }
+
+    #[test]
+    fn counter_stops_at_overshoot_without_overflow() {
+        let mut counter = Counter::with_step(250u8, 3).with_max(254);
+        assert_eq!(counter.try_generate_n(2), Some(vec![250, 253]));
+        assert_eq!(counter.try_generate(), None);
+        assert_eq!(counter.try_generate(), None);
+    }
+
+    #[test]
+    fn counter_stops_at_type_max() {
+        let mut counter = Counter::new(u8::MAX - 1);
+        assert_eq!(counter.try_generate_n(2), Some(vec![254, 255]));
+        assert_eq!(counter.try_generate(), None);
+        let mut counter = Counter::with_step(i8::MIN + 1, -1);
+        assert_eq!(counter.try_generate_n(2), Some(vec![-127, -128]));
+        assert_eq!(counter.try_generate(), None);
+    }
+
+    #[test]
+    fn counter_with_start_above_max_is_empty() {
+        let mut counter = Counter::new(5u8).with_max(4);
+        assert_eq!(counter.try_generate(), None);
+    }
+}